@@ -0,0 +1,73 @@
+//! Compares the table based escape code encoder with the `write!` formatting it replaced.
+//!
+//! Run with `cargo bench --bench encode`.
+
+use std::{
+    hint::black_box,
+    io::Write,
+    time::{Duration, Instant},
+};
+
+use ansistream::{AnsiWrite, FC_RED};
+
+const ITERATIONS: u32 = 1_000_000;
+
+/// Run f ITERATIONS times over a reused buffer, and returns the mean time of a call.
+fn bench(mut f: impl FnMut(&mut Vec<u8>)) -> Duration {
+    let mut buffer = Vec::with_capacity(1024);
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        buffer.clear();
+        f(black_box(&mut buffer));
+    }
+    start.elapsed() / ITERATIONS
+}
+
+fn compare(name: &str, old: impl FnMut(&mut Vec<u8>), new: impl FnMut(&mut Vec<u8>)) {
+    let old = bench(old);
+    let new = bench(new);
+    println!(
+        "{name:<16} write!: {:>6.1?}  table: {:>6.1?}  speedup: {:.2}x",
+        old,
+        new,
+        old.as_secs_f64() / new.as_secs_f64()
+    );
+}
+
+fn main() {
+    compare(
+        "attribute",
+        |b| write!(b, "\x1b[{}m", black_box(FC_RED)).unwrap(),
+        |b| b.write_attribute(black_box(FC_RED)).unwrap(),
+    );
+    compare(
+        "256 color",
+        |b| write!(b, "\x1b[38;5;{}m", black_box(208)).unwrap(),
+        |b| {
+            b.write_color_256(38, black_box(208), Default::default())
+                .unwrap()
+        },
+    );
+    compare(
+        "rgb color",
+        |b| {
+            let (r, g, bl) = black_box((255, 128, 7));
+            write!(b, "\x1b[38;2;{r};{g};{bl}m").unwrap()
+        },
+        |b| {
+            let (r, g, bl) = black_box((255, 128, 7));
+            b.write_color_rgb(38, r, g, bl, Default::default()).unwrap()
+        },
+    );
+    compare(
+        "cursor position",
+        |b| {
+            let (row, col) = black_box((24, 80));
+            write!(b, "\x1b[{row};{col}H").unwrap()
+        },
+        |b| {
+            let (row, col) = black_box((24, 80));
+            b.cursor_position(row, col).unwrap()
+        },
+    );
+}
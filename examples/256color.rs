@@ -1,10 +1,7 @@
-
-use ansistream::{FC_BLACK, FC_WHITE};
+use ansistream::{xterm_cube, xterm_gray, FC_BLACK, FC_WHITE};
 use std::io::Write;
 fn main() -> std::io::Result<()> {
-    let mut astream = ansistream::AnsiEscapeStream::new(
-        std::io::stdout().lock()
-    );
+    let mut astream = ansistream::AnsiEscapeStream::new(std::io::stdout().lock());
 
     astream.write_string("Printing 256 color(16 bit) table\n\n")?;
     astream.write_string("Printing standard and extended colors:\n\n")?;
@@ -22,35 +19,30 @@ fn main() -> std::io::Result<()> {
 
     astream.write_string("\n\nPrinting 256 color:\n\n")?;
 
-    let mut fg = 0;
-    for i in 0..=215 {
-        let v = i + 16;
-        if i != 0 {
-            if i % 18 == 0 {
-                fg = 0;
+    // six rows of the color cube per red level, three green levels per row
+    for r in 0..6 {
+        for g in 0..6 {
+            if g % 3 == 0 && (r, g) != (0, 0) {
                 writeln!(&mut *astream)?;
             }
-            if i % 36 == 0 {
-                fg = 255;
+            for b in 0..6 {
+                let v = u16::from(xterm_cube(r, g, b));
+                astream.write_attribute(if g < 3 { FC_WHITE } else { FC_BLACK })?;
+                astream.write_text_bc256_fmt(v, format_args!("{v:<4}"))?;
             }
         }
-        astream.write_attribute(fg)?;
-        astream.write_text_bc256_fmt(v, format_args!("{v:<4}"))?;
     }
 
     astream.write_string("\n\nPrinting gray color:\n\n")?;
 
-    for i in 0..=23 {
-        if i < 12 {
-            fg = FC_WHITE;
-        } else {
-            fg = FC_BLACK;
-        }
-
-        let ie = i + 232;
+    for step in 0..24 {
+        let fg = if step < 12 { FC_WHITE } else { FC_BLACK };
+        let v = u16::from(xterm_gray(step));
         astream.write_attribute(fg)?;
-        astream.write_text_bc256_fmt(ie, format_args!("{ie:<4}"))?;
+        astream.write_text_bc256_fmt(v, format_args!("{v:<4}"))?;
     }
 
+    astream.reset_all_attributes()?;
+
     Ok(())
 }
@@ -1,8 +1,8 @@
 use std::io::{self, Write};
 
 use ansistream::{
-    TS_BLINK, TS_BOLD, TS_DIM, TS_HIDDEN, TS_INVERT, TS_ITALIC, TS_OVERLINE, TS_STRIKE,
-    TS_UNDERLINE,
+    TS_BLINK, TS_BOLD, TS_DIM, TS_DOUBLE_UNDERLINE, TS_ENCIRCLED, TS_FRAKTUR, TS_FRAMED, TS_HIDDEN,
+    TS_INVERT, TS_ITALIC, TS_OVERLINE, TS_STRIKE, TS_SUBSCRIPT, TS_SUPERSCRIPT, TS_UNDERLINE,
 };
 
 fn main() -> io::Result<()> {
@@ -47,6 +47,40 @@ fn main() -> io::Result<()> {
     astream.write_attribute(TS_STRIKE)?;
     astream.write_string("This is a strike styled text\n")?;
     astream.reset_all_attributes()?;
+    writeln!(&mut *astream)?;
+
+    astream.write_string("Rarely supported styles:\n\n")?;
+    astream.write_attribute(TS_FRAKTUR)?;
+    astream.write_string("This is a fraktur styled text\n")?;
+    astream.reset_all_attributes()?;
+
+    astream.write_attribute(TS_DOUBLE_UNDERLINE)?;
+    astream.write_string("This is a double underline styled text\n")?;
+    astream.reset_all_attributes()?;
+
+    astream.write_attribute(TS_FRAMED)?;
+    astream.write_string("This is a framed styled text\n")?;
+    astream.reset_all_attributes()?;
+
+    astream.write_attribute(TS_ENCIRCLED)?;
+    astream.write_string("This is a encircled styled text\n")?;
+    astream.reset_all_attributes()?;
+
+    astream.write_string("This is a ")?;
+    astream.write_attribute(TS_SUPERSCRIPT)?;
+    astream.write_string("superscript")?;
+    astream.reset_all_attributes()?;
+    astream.write_string(" and ")?;
+    astream.write_attribute(TS_SUBSCRIPT)?;
+    astream.write_string("subscript")?;
+    astream.reset_all_attributes()?;
+    astream.write_string(" styled text\n")?;
+
+    for font in 1..=9 {
+        astream.write_font(font)?;
+        astream.write_string(&format!("This is alternative font {font}\n"))?;
+    }
+    astream.write_font(0)?;
 
     Ok(())
 }
@@ -1,7 +1,7 @@
 use std::io;
 use std::io::Write;
 
-use ansistream::FC_DARK_GRAY;
+use ansistream::{Gradient, FC_DARK_GRAY};
 
 fn hex2rgb(hex: u32) -> (u16, u16, u16) {
     let r = (hex >> 16) as u16;
@@ -23,7 +23,9 @@ fn main() -> io::Result<()> {
         0x9e3740, 0xf3d99e, 0xb1b88c, 0x55887f, 0x2b2e37,
     ];
 
-    astream.write_string("Printing a common brazilian color palette:\n\n")?;
+    let title = Gradient::new([(0x00, 0x9c, 0x3b), (0xff, 0xdf, 0x00), (0x00, 0x27, 0x76)]);
+    astream.write_text_fc_gradient(&title, "Printing a common brazilian color palette:")?;
+    astream.write_string("\n\n")?;
 
     for (idx, &c) in palettes.iter().enumerate() {
         if idx % 5 == 0 {
@@ -0,0 +1,67 @@
+//! Changes made to colors before they are written: the color depth, the minimum contrast and
+//! the simulated color vision deficiency.
+
+use crate::{
+    colorspace::{downgrade, NearestCache},
+    contrast, cvd, Color, ColorDepth, ColorScheme, Deficiency, Rgb, Style,
+};
+
+/// The color options of a writer, applied to every color it writes.
+#[derive(Debug, Default)]
+pub(crate) struct ColorAdjust {
+    pub(crate) depth: ColorDepth,
+    pub(crate) scheme: ColorScheme,
+    pub(crate) min_contrast: Option<f32>,
+    pub(crate) simulation: Option<Deficiency>,
+    pub(crate) nearest: Option<NearestCache>,
+}
+
+impl ColorAdjust {
+    /// Returns the color nearest to rgb which the color depth can show.
+    pub(crate) fn downgrade(&mut self, rgb: Rgb) -> Color {
+        downgrade(rgb, self.depth, &mut self.nearest)
+    }
+
+    /// Returns style with a foreground which has the minimum contrast, and with the colors
+    /// seen with the simulated deficiency. Styles without colors are not changed.
+    pub(crate) fn style(&mut self, style: &Style) -> Style {
+        let mut style = *style;
+        if style.foreground.is_none() && style.background.is_none() {
+            return style;
+        }
+        if let Some(ratio) = self.min_contrast {
+            let foreground = style.foreground.unwrap_or(Color::Default);
+            let background = style.background.unwrap_or(Color::Default);
+            let (scheme, depth) = (&self.scheme, self.depth);
+            if let Some(color) =
+                contrast::contrast_color(foreground, background, ratio, scheme, depth)
+            {
+                style.foreground = Some(color);
+            }
+        }
+        if let Some(deficiency) = self.simulation {
+            let mut simulate = |color: Option<Color>| {
+                color.map(|color| {
+                    cvd::simulate_color(
+                        color,
+                        deficiency,
+                        &self.scheme,
+                        self.depth,
+                        &mut self.nearest,
+                    )
+                })
+            };
+            style.foreground = simulate(style.foreground);
+            style.background = simulate(style.background);
+        }
+        style
+    }
+
+    /// Returns the adjusted style if its colors are changed, or `None` to write style as it is.
+    pub(crate) fn changed(&mut self, style: Style) -> Option<Style> {
+        if self.min_contrast.is_none() && self.simulation.is_none() {
+            return None;
+        }
+        Some(self.style(&style)).filter(|adjusted| *adjusted != style)
+    }
+}
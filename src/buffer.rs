@@ -0,0 +1,301 @@
+//! Buffered writer used by `AnsiEscapeStream`, with a configurable flush policy.
+
+use std::{
+    io::{self, Write},
+    string::FromUtf8Error,
+    time::{Duration, Instant},
+};
+
+/// Default capacity of a `StreamBuffer`, the same as `std::io::BufWriter`.
+pub(crate) const DEFAULT_CAPACITY: usize = 8 * 1024;
+
+/// When a `StreamBuffer` writes its buffered data to the writer, besides when it is full or
+/// `flush` is called.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FlushPolicy {
+    /// Flush only when the buffer is full or `flush` is called.
+    #[default]
+    Manual,
+    /// Flush after every write which contains a new line, like `std::io::LineWriter`.
+    Line,
+    /// Flush when the buffered data reaches the given number of bytes.
+    Threshold(usize),
+    /// Flush on a write when the oldest buffered data is older than the given duration.
+    /// Nothing is flushed between writes, as there is no background thread.
+    Interval(Duration),
+}
+
+/// A buffer in front of a writer. It works like `std::io::BufWriter`, and also flushes
+/// following its `FlushPolicy`. Buffered data is flushed on drop, ignoring errors.
+#[derive(Debug)]
+pub struct StreamBuffer<W: Write> {
+    writer: W,
+    buffer: Vec<u8>,
+    capacity: usize,
+    policy: FlushPolicy,
+    pending_since: Option<Instant>,
+    writes: u64,
+    /// Bumped each time the buffered data is discarded or taken out.
+    generation: u64,
+}
+
+/// A position in the buffered data of a `StreamBuffer`, to roll back to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Checkpoint {
+    len: usize,
+    writes: u64,
+    generation: u64,
+}
+
+impl<W: Write> StreamBuffer<W> {
+    /// Initializes a StreamBuffer with the default capacity of 8 KiB.
+    pub fn new(writer: W) -> Self {
+        Self::with_capacity(DEFAULT_CAPACITY, writer)
+    }
+
+    /// Initializes a StreamBuffer which holds up to capacity bytes before writing to writer.
+    pub fn with_capacity(capacity: usize, writer: W) -> Self {
+        Self {
+            writer,
+            buffer: Vec::with_capacity(capacity),
+            capacity,
+            policy: FlushPolicy::default(),
+            pending_since: None,
+            writes: 0,
+            generation: 0,
+        }
+    }
+
+    /// Returns the buffered data which was not written to the writer yet.
+    pub fn buffer(&self) -> &[u8] {
+        &self.buffer
+    }
+
+    /// Returns the number of bytes the buffer holds before writing to the writer.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns the flush policy.
+    pub fn flush_policy(&self) -> FlushPolicy {
+        self.policy
+    }
+
+    /// Set the flush policy, which applies from the next write on.
+    pub fn set_flush_policy(&mut self, policy: FlushPolicy) {
+        self.policy = policy;
+    }
+
+    /// Returns a reference to the writer.
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    /// Returns a mutable reference to the writer. Writing to it directly bypasses the buffered data.
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.writer
+    }
+
+    /// Discard the buffered data. The capacity remains the same.
+    pub fn clear(&mut self) {
+        self.buffer.clear();
+        self.pending_since = None;
+        self.generation += 1;
+    }
+
+    /// Returns the current position of the buffered data, to discard what is written after it
+    /// with `rollback`.
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            len: self.buffer.len(),
+            writes: self.writes,
+            generation: self.generation,
+        }
+    }
+
+    /// Discard the data buffered after the checkpoint. Returns false and discards nothing if
+    /// data was written to the writer since the checkpoint, as it can not be taken back, or if
+    /// the buffered data was cleared or taken out since the checkpoint.
+    pub fn rollback(&mut self, checkpoint: Checkpoint) -> bool {
+        if checkpoint.writes != self.writes
+            || checkpoint.generation != self.generation
+            || checkpoint.len > self.buffer.len()
+        {
+            return false;
+        }
+        self.buffer.truncate(checkpoint.len);
+        if self.buffer.is_empty() {
+            self.pending_since = None;
+        }
+        true
+    }
+
+    /// Take the buffered data out, without writing it to the writer.
+    pub fn take(&mut self) -> Vec<u8> {
+        self.pending_since = None;
+        self.generation += 1;
+        std::mem::replace(&mut self.buffer, Vec::with_capacity(self.capacity))
+    }
+
+    /// Take the buffered data out as a string, without writing it to the writer. If it is not
+    /// valid utf-8, the data is returned in the error.
+    pub fn take_string(&mut self) -> Result<String, FromUtf8Error> {
+        String::from_utf8(self.take())
+    }
+
+    /// Write the buffered data to the writer, without flushing the writer.
+    fn flush_buf(&mut self) -> io::Result<()> {
+        if !self.buffer.is_empty() {
+            self.writes += 1;
+            self.writer.write_all(&self.buffer)?;
+            self.buffer.clear();
+        }
+        self.pending_since = None;
+        Ok(())
+    }
+
+    /// Returns true if the policy asks for a flush after data was buffered.
+    fn should_flush(&self, data: &[u8]) -> bool {
+        match self.policy {
+            FlushPolicy::Manual => false,
+            FlushPolicy::Line => data.contains(&b'\n'),
+            FlushPolicy::Threshold(threshold) => self.buffer.len() >= threshold,
+            FlushPolicy::Interval(interval) => self
+                .pending_since
+                .is_some_and(|since| since.elapsed() >= interval),
+        }
+    }
+}
+
+impl<W: Write> Write for StreamBuffer<W> {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        if self.buffer.len() + data.len() > self.capacity {
+            self.flush_buf()?;
+        }
+        if data.len() >= self.capacity {
+            self.writes += 1;
+            self.writer.write_all(data)?;
+        } else {
+            if self.buffer.is_empty() {
+                self.pending_since = Some(Instant::now());
+            }
+            self.buffer.extend_from_slice(data);
+        }
+        if self.should_flush(data) {
+            self.flush()?;
+        }
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.flush_buf()?;
+        self.writer.flush()
+    }
+}
+
+impl<W: Write> Drop for StreamBuffer<W> {
+    fn drop(&mut self) {
+        let _ = self.flush_buf();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_capacity() {
+        let mut buffer = StreamBuffer::with_capacity(4, Vec::<u8>::new());
+        assert_eq!(4, buffer.capacity());
+        buffer.write_all(b"abc").unwrap();
+        assert_eq!(b"abc", buffer.buffer());
+        // does not fit, the buffered data is written first
+        buffer.write_all(b"de").unwrap();
+        assert_eq!(b"abc", buffer.get_ref().as_slice());
+        // larger than the capacity, written straight to the writer
+        buffer.write_all(b"fghij").unwrap();
+        assert_eq!(b"abcdefghij", buffer.get_ref().as_slice());
+        assert!(buffer.buffer().is_empty());
+    }
+
+    #[test]
+    fn test_line_policy() {
+        let mut buffer = StreamBuffer::new(Vec::<u8>::new());
+        buffer.set_flush_policy(FlushPolicy::Line);
+        buffer.write_all(b"50%").unwrap();
+        assert!(buffer.get_ref().is_empty());
+        buffer.write_all(b"\n").unwrap();
+        assert_eq!(b"50%\n", buffer.get_ref().as_slice());
+        assert!(buffer.buffer().is_empty());
+    }
+
+    #[test]
+    fn test_threshold_policy() {
+        let mut buffer = StreamBuffer::new(Vec::<u8>::new());
+        buffer.set_flush_policy(FlushPolicy::Threshold(4));
+        buffer.write_all(b"abc").unwrap();
+        assert!(buffer.get_ref().is_empty());
+        buffer.write_all(b"d").unwrap();
+        assert_eq!(b"abcd", buffer.get_ref().as_slice());
+    }
+
+    #[test]
+    fn test_interval_policy() {
+        let mut buffer = StreamBuffer::new(Vec::<u8>::new());
+        buffer.set_flush_policy(FlushPolicy::Interval(Duration::from_secs(3600)));
+        buffer.write_all(b"abc").unwrap();
+        assert!(buffer.get_ref().is_empty());
+        buffer.set_flush_policy(FlushPolicy::Interval(Duration::ZERO));
+        buffer.write_all(b"d").unwrap();
+        assert_eq!(b"abcd", buffer.get_ref().as_slice());
+    }
+
+    #[test]
+    fn test_checkpoint_rollback() {
+        let mut buffer = StreamBuffer::with_capacity(8, Vec::<u8>::new());
+        buffer.write_all(b"ab").unwrap();
+        let checkpoint = buffer.checkpoint();
+        buffer.write_all(b"cd").unwrap();
+        assert!(buffer.rollback(checkpoint));
+        assert_eq!(b"ab", buffer.buffer());
+        // data written to the writer after the checkpoint can not be rolled back
+        buffer.write_all(b"cdefghi").unwrap();
+        assert!(!buffer.rollback(checkpoint));
+        assert_eq!(b"cdefghi", buffer.buffer());
+    }
+
+    #[test]
+    fn test_rollback_after_discard() {
+        let mut buffer = StreamBuffer::new(Vec::<u8>::new());
+        buffer.write_all(b"abcdef").unwrap();
+        let checkpoint = buffer.checkpoint();
+        buffer.take();
+        buffer.write_all(b"0123456789").unwrap();
+        // the checkpoint was in the data taken out, not in the new data
+        assert!(!buffer.rollback(checkpoint));
+        assert_eq!(b"0123456789", buffer.buffer());
+        let checkpoint = buffer.checkpoint();
+        buffer.clear();
+        buffer.write_all(b"0123456789").unwrap();
+        assert!(!buffer.rollback(checkpoint));
+        let checkpoint = buffer.checkpoint();
+        buffer.take_string().unwrap();
+        buffer.write_all(b"0123456789").unwrap();
+        assert!(!buffer.rollback(checkpoint));
+        assert_eq!(b"0123456789", buffer.buffer());
+    }
+
+    #[test]
+    fn test_take_and_clear() {
+        let mut buffer = StreamBuffer::new(Vec::<u8>::new());
+        buffer.write_all(b"frame").unwrap();
+        assert_eq!("frame", buffer.take_string().unwrap());
+        buffer.write_all(&[0xff]).unwrap();
+        assert_eq!(vec![0xff], buffer.take_string().unwrap_err().into_bytes());
+        buffer.write_all(b"abc").unwrap();
+        buffer.clear();
+        assert!(buffer.take().is_empty());
+        buffer.flush().unwrap();
+        assert!(buffer.get_ref().is_empty());
+    }
+}
@@ -0,0 +1,471 @@
+//! Color values which can be used as foreground or background of a `Style`.
+
+use core::{fmt, str::FromStr};
+
+use crate::{
+    color_names::NAMED_COLORS,
+    palette::{Rgb, XTERM_PALETTE},
+    BC_DEFAULT, BC_RICH_COLORS, FC_DEFAULT, FC_RICH_COLORS,
+};
+
+/// A terminal color. The 16 named colors follow the names of the `FC_*` and `BC_*` constants.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Color {
+    Black,
+    Red,
+    Green,
+    Brown,
+    Blue,
+    Magenta,
+    Cyan,
+    LightGray,
+    DarkGray,
+    LightRed,
+    LightGreen,
+    Yellow,
+    LightBlue,
+    LightMagenta,
+    LightCyan,
+    White,
+    /// An index of the 256 color palette.
+    Ansi256(u8),
+    /// A 24 bit RGB color.
+    Rgb(u8, u8, u8),
+    /// The default color of the terminal, FC_DEFAULT or BC_DEFAULT.
+    Default,
+}
+
+impl Color {
+    /// The 16 named colors, in palette order.
+    pub const NAMED: [Color; 16] = [
+        Color::Black,
+        Color::Red,
+        Color::Green,
+        Color::Brown,
+        Color::Blue,
+        Color::Magenta,
+        Color::Cyan,
+        Color::LightGray,
+        Color::DarkGray,
+        Color::LightRed,
+        Color::LightGreen,
+        Color::Yellow,
+        Color::LightBlue,
+        Color::LightMagenta,
+        Color::LightCyan,
+        Color::White,
+    ];
+
+    /// Returns the foreground SGR code of a named color, like FC_RED for `Color::Red`.
+    /// For `Ansi256` and `Rgb` colors, FC_RICH_COLORS is returned.
+    pub fn fc_code(self) -> u16 {
+        match self {
+            Color::Ansi256(_) | Color::Rgb(..) => FC_RICH_COLORS,
+            Color::Default => FC_DEFAULT,
+            named => {
+                let index = Color::NAMED.iter().position(|&c| c == named).unwrap_or(0) as u16;
+                if index < 8 {
+                    30 + index
+                } else {
+                    82 + index
+                }
+            }
+        }
+    }
+
+    /// Returns the background SGR code of a named color, like BC_RED for `Color::Red`.
+    /// For `Ansi256` and `Rgb` colors, BC_RICH_COLORS is returned.
+    pub fn bc_code(self) -> u16 {
+        match self {
+            Color::Ansi256(_) | Color::Rgb(..) => BC_RICH_COLORS,
+            Color::Default => BC_DEFAULT,
+            named => named.fc_code() + 10,
+        }
+    }
+
+    /// The names of the 16 named colors, in palette order.
+    pub(crate) const NAMES: [&'static str; 16] = [
+        "black",
+        "red",
+        "green",
+        "brown",
+        "blue",
+        "magenta",
+        "cyan",
+        "light_gray",
+        "dark_gray",
+        "light_red",
+        "light_green",
+        "yellow",
+        "light_blue",
+        "light_magenta",
+        "light_cyan",
+        "white",
+    ];
+
+    /// Parse a color word: a name like `red` or `light_blue`, `default`, an index of the 256
+    /// color palette, or a RGB color like `#ff8800`.
+    pub(crate) fn from_word(word: &str) -> Option<Color> {
+        if let Some(hex) = word.strip_prefix('#') {
+            return Color::from_hex(hex);
+        }
+        if word.eq_ignore_ascii_case("default") {
+            return Some(Color::Default);
+        }
+        if let Ok(n) = word.parse::<u8>() {
+            return Some(Color::Ansi256(n));
+        }
+        Color::NAMES
+            .iter()
+            .position(|name| name.eq_ignore_ascii_case(word))
+            .map(|index| Color::NAMED[index])
+    }
+
+    /// Parse a color of the git config syntax: `black`, `red`, `green`, `yellow`, `blue`,
+    /// `magenta`, `cyan` and `white` for SGR 30-37, the same names prefixed by `bright` for
+    /// SGR 90-97, `default`, 0-255 and `#rrggbb`. Note that git names follow the SGR codes,
+    /// so `yellow` is `Color::Brown` and `white` is `Color::LightGray`.
+    pub(crate) fn from_git_name(word: &str) -> Option<Color> {
+        if let Some(hex) = word.strip_prefix('#') {
+            return Color::from_hex(hex);
+        }
+        if word.eq_ignore_ascii_case("default") {
+            return Some(Color::Default);
+        }
+        if let Ok(n) = word.parse::<u8>() {
+            return Some(Color::Ansi256(n));
+        }
+        let lower_prefix = |prefix: &str| {
+            word.get(..prefix.len())
+                .filter(|head| head.eq_ignore_ascii_case(prefix))
+                .map(|_| &word[prefix.len()..])
+        };
+        let (name, offset) = match lower_prefix("bright") {
+            Some(name) => (name, 8),
+            None => (word, 0),
+        };
+        GIT_NAMES
+            .iter()
+            .position(|n| n.eq_ignore_ascii_case(name))
+            .map(|index| Color::NAMED[index + offset])
+    }
+
+    /// Returns a value which displays the color in the git config syntax.
+    pub(crate) fn git_name(self) -> impl fmt::Display {
+        GitName(self)
+    }
+
+    /// Parse the 3 or 6 hexadecimal digits of a RGB color, without the `#`.
+    pub(crate) fn from_hex(hex: &str) -> Option<Color> {
+        if !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return None;
+        }
+        let channel = |digits: &str| u8::from_str_radix(digits, 16).ok();
+        match hex.len() {
+            3 => {
+                let short = |i: usize| channel(&hex[i..i + 1]).map(|n| n * 17);
+                Some(Color::Rgb(short(0)?, short(1)?, short(2)?))
+            }
+            6 => Some(Color::Rgb(
+                channel(&hex[0..2])?,
+                channel(&hex[2..4])?,
+                channel(&hex[4..6])?,
+            )),
+            _ => None,
+        }
+    }
+
+    /// Returns the RGB value of the color. Named and `Ansi256` colors are looked up in
+    /// `XTERM_PALETTE`, and `Default` has no value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ansistream::{xterm_gray, Color};
+    ///
+    /// assert_eq!(Some((205, 0, 0)), Color::Red.to_rgb());
+    /// assert_eq!(Some((128, 128, 128)), Color::Ansi256(xterm_gray(12)).to_rgb());
+    /// assert_eq!(None, Color::Default.to_rgb());
+    /// ```
+    pub fn to_rgb(self) -> Option<Rgb> {
+        match self {
+            Color::Ansi256(index) => Some(XTERM_PALETTE[usize::from(index)]),
+            Color::Rgb(r, g, b) => Some((r, g, b)),
+            Color::Default => None,
+            named => Color::NAMED
+                .iter()
+                .position(|&c| c == named)
+                .map(|index| XTERM_PALETTE[index]),
+        }
+    }
+
+    /// Returns the named color of a foreground or background SGR code, like `Color::Red` for
+    /// FC_RED or BC_RED, or `Color::Default` for FC_DEFAULT or BC_DEFAULT.
+    pub fn from_code(code: u16) -> Option<Color> {
+        let index = match code {
+            FC_DEFAULT | BC_DEFAULT => return Some(Color::Default),
+            30..=37 => code - 30,
+            40..=47 => code - 40,
+            90..=97 => code - 82,
+            100..=107 => code - 92,
+            _ => return None,
+        };
+        Some(Color::NAMED[index as usize])
+    }
+}
+
+/// Why a color could not be parsed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseColorErrorKind {
+    /// A `#` color without 3 or 6 hexadecimal digits.
+    InvalidHex,
+    /// A `rgb(…)` or `hsl(…)` color with missing or out of range components.
+    InvalidFunction,
+    /// A name which is not a CSS or X11 color.
+    UnknownName,
+}
+
+/// A color which could not be parsed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseColorError {
+    kind: ParseColorErrorKind,
+}
+
+impl ParseColorError {
+    /// Returns why the color could not be parsed.
+    pub fn kind(&self) -> ParseColorErrorKind {
+        self.kind
+    }
+}
+
+impl fmt::Display for ParseColorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self.kind {
+            ParseColorErrorKind::InvalidHex => "expected 3 or 6 hexadecimal digits after #",
+            ParseColorErrorKind::InvalidFunction => "invalid rgb() or hsl() color",
+            ParseColorErrorKind::UnknownName => "unknown color name",
+        })
+    }
+}
+
+impl core::error::Error for ParseColorError {}
+
+impl FromStr for Color {
+    type Err = ParseColorError;
+
+    /// Parse a RGB color from `#rgb`, `#rrggbb`, `rgb(…)`, `hsl(…)`, a CSS color name or a X11
+    /// `rgb.txt` color name. The result is always a `Color::Rgb`, whose components can be
+    /// written with `write_text_fcrgb` and `write_text_bcrgb`.
+    ///
+    /// `rgb` components are 0-255 or percentages, and `hsl` takes a hue in degrees and two
+    /// percentages, separated by commas or spaces. Names ignore case, spaces and underscores.
+    /// For names defined by both lists, like `gray`, the CSS value is used.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ansistream::Color;
+    ///
+    /// assert_eq!(Ok(Color::Rgb(255, 136, 0)), "#f80".parse());
+    /// assert_eq!(Ok(Color::Rgb(255, 0, 128)), "rgb(255, 0, 50%)".parse());
+    /// assert_eq!(Ok(Color::Rgb(0, 255, 0)), "hsl(120deg 100% 50%)".parse());
+    /// assert_eq!(Ok(Color::Rgb(100, 149, 237)), "CornflowerBlue".parse());
+    /// assert_eq!(Ok(Color::Rgb(238, 173, 14)), "dark goldenrod 2".parse());
+    /// ```
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let error = |kind| ParseColorError { kind };
+        let text = text.trim();
+        if let Some(hex) = text.strip_prefix('#') {
+            return Color::from_hex(hex).ok_or(error(ParseColorErrorKind::InvalidHex));
+        }
+        if let Some(args) = function_args(text, "rgb") {
+            return parse_rgb(args).ok_or(error(ParseColorErrorKind::InvalidFunction));
+        }
+        if let Some(args) = function_args(text, "hsl") {
+            return parse_hsl(args).ok_or(error(ParseColorErrorKind::InvalidFunction));
+        }
+        let name = text
+            .bytes()
+            .filter(|b| !matches!(b, b' ' | b'_' | b'-'))
+            .map(|b| b.to_ascii_lowercase());
+        NAMED_COLORS
+            .binary_search_by(|(key, _)| key.bytes().cmp(name.clone()))
+            .map(|index| {
+                let [_, r, g, b] = NAMED_COLORS[index].1.to_be_bytes();
+                Color::Rgb(r, g, b)
+            })
+            .map_err(|_| error(ParseColorErrorKind::UnknownName))
+    }
+}
+
+/// Returns the arguments of a color function like `rgb(1, 2, 3)`, ignoring case.
+fn function_args<'a>(text: &'a str, name: &str) -> Option<&'a str> {
+    let prefix = text.get(..name.len())?;
+    let rest = text.get(name.len()..)?.strip_prefix('(')?;
+    if !prefix.eq_ignore_ascii_case(name) {
+        return None;
+    }
+    rest.strip_suffix(')')
+}
+
+/// Split exactly three components separated by commas or spaces.
+fn components(args: &str) -> Option<[&str; 3]> {
+    let mut parts = args
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|part| !part.is_empty());
+    let components = [parts.next()?, parts.next()?, parts.next()?];
+    parts.next().is_none().then_some(components)
+}
+
+/// Parse a number, or a percentage of max when it ends with `%`. The result is in 0..=max.
+fn parse_component(text: &str, max: f32) -> Option<f32> {
+    let value = match text.strip_suffix('%') {
+        Some(percent) => percent.parse::<f32>().ok()? * max / 100.0,
+        None => text.parse::<f32>().ok()?,
+    };
+    (0.0..=max).contains(&value).then_some(value)
+}
+
+/// Round a channel in 0..=255 to the nearest integer.
+fn round_channel(value: f32) -> u8 {
+    (value + 0.5) as u8
+}
+
+fn parse_rgb(args: &str) -> Option<Color> {
+    let [r, g, b] = components(args)?;
+    let channel = |text| parse_component(text, 255.0).map(round_channel);
+    Some(Color::Rgb(channel(r)?, channel(g)?, channel(b)?))
+}
+
+fn parse_hsl(args: &str) -> Option<Color> {
+    let [h, s, l] = components(args)?;
+    let h = h.strip_suffix("deg").unwrap_or(h).parse::<f32>().ok()?;
+    if !h.is_finite() {
+        return None;
+    }
+    let s = parse_component(s.strip_suffix('%')?, 100.0)? / 100.0;
+    let l = parse_component(l.strip_suffix('%')?, 100.0)? / 100.0;
+    let h = h % 360.0;
+    let h = if h < 0.0 { h + 360.0 } else { h };
+    let a = s * l.min(1.0 - l);
+    let channel = |n: f32| {
+        let k = (n + h / 30.0) % 12.0;
+        let value = l - a * (k - 3.0).min(9.0 - k).clamp(-1.0, 1.0);
+        round_channel(value * 255.0)
+    };
+    Some(Color::Rgb(channel(0.0), channel(8.0), channel(4.0)))
+}
+
+/// The git names of the 8 colors of SGR 30-37.
+const GIT_NAMES: [&str; 8] = [
+    "black", "red", "green", "yellow", "blue", "magenta", "cyan", "white",
+];
+
+struct GitName(Color);
+
+impl fmt::Display for GitName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            Color::Ansi256(n) => write!(f, "{n}"),
+            Color::Rgb(r, g, b) => write!(f, "#{r:02x}{g:02x}{b:02x}"),
+            Color::Default => f.write_str("default"),
+            named => {
+                let index = Color::NAMED.iter().position(|&c| c == named).unwrap_or(0);
+                if index >= 8 {
+                    f.write_str("bright")?;
+                }
+                f.write_str(GIT_NAMES[index % 8])
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::{BC_LIGHT_CYAN, BC_RED, FC_BLACK, FC_WHITE, FC_YELLOW};
+
+    #[test]
+    fn test_sgr_codes() {
+        assert_eq!(FC_BLACK, Color::Black.fc_code());
+        assert_eq!(FC_YELLOW, Color::Yellow.fc_code());
+        assert_eq!(FC_WHITE, Color::White.fc_code());
+        assert_eq!(BC_RED, Color::Red.bc_code());
+        assert_eq!(BC_LIGHT_CYAN, Color::LightCyan.bc_code());
+        assert_eq!(FC_RICH_COLORS, Color::Rgb(1, 2, 3).fc_code());
+        assert_eq!(BC_RICH_COLORS, Color::Ansi256(9).bc_code());
+    }
+
+    #[test]
+    fn test_from_code() {
+        for color in Color::NAMED {
+            assert_eq!(Some(color), Color::from_code(color.fc_code()));
+            assert_eq!(Some(color), Color::from_code(color.bc_code()));
+        }
+        assert_eq!(None, Color::from_code(38));
+        assert_eq!(Some(Color::Default), Color::from_code(BC_DEFAULT));
+    }
+
+    #[test]
+    fn test_from_word() {
+        assert_eq!(Some(Color::LightBlue), Color::from_word("light_blue"));
+        assert_eq!(Some(Color::Red), Color::from_word("RED"));
+        assert_eq!(Some(Color::Ansi256(208)), Color::from_word("208"));
+        assert_eq!(Some(Color::Rgb(255, 136, 0)), Color::from_word("#ff8800"));
+        assert_eq!(None, Color::from_word("#ff88"));
+        assert_eq!(None, Color::from_word("256"));
+        assert_eq!(None, Color::from_word("purple"));
+        assert_eq!(Some(Color::Default), Color::from_word("default"));
+    }
+
+    #[test]
+    fn test_from_str() {
+        let parse = |text: &str| text.parse::<Color>();
+        assert_eq!(Ok(Color::Rgb(0x12, 0x34, 0x56)), parse("#123456"));
+        assert_eq!(Ok(Color::Rgb(0xaa, 0xbb, 0xcc)), parse(" #ABC "));
+        assert_eq!(Ok(Color::Rgb(1, 2, 3)), parse("RGB(1,2,3)"));
+        assert_eq!(Ok(Color::Rgb(255, 128, 0)), parse("rgb(100% 50% 0%)"));
+        assert_eq!(Ok(Color::Rgb(255, 0, 0)), parse("hsl(360, 100%, 50%)"));
+        assert_eq!(Ok(Color::Rgb(0, 0, 255)), parse("hsl(-120 100% 50%)"));
+        assert_eq!(Ok(Color::Rgb(191, 64, 64)), parse("hsl(0, 50%, 50%)"));
+        assert_eq!(Ok(Color::Rgb(128, 128, 128)), parse("hsl(200, 0%, 50%)"));
+        // css values win over x11 values
+        assert_eq!(Ok(Color::Rgb(0, 128, 0)), parse("green"));
+        assert_eq!(Ok(Color::Rgb(102, 51, 153)), parse("rebeccapurple"));
+        assert_eq!(Ok(Color::Rgb(255, 231, 186)), parse("wheat1"));
+        assert_eq!(Ok(Color::Rgb(119, 136, 153)), parse("light_slate_gray"));
+
+        let kind = |text: &str| parse(text).unwrap_err().kind();
+        assert_eq!(ParseColorErrorKind::InvalidHex, kind("#12345"));
+        assert_eq!(ParseColorErrorKind::InvalidHex, kind("#ggg"));
+        assert_eq!(ParseColorErrorKind::InvalidFunction, kind("rgb(1, 2)"));
+        assert_eq!(ParseColorErrorKind::InvalidFunction, kind("rgb(1, 2, 256)"));
+        assert_eq!(
+            ParseColorErrorKind::InvalidFunction,
+            kind("hsl(0, 50, 50%)")
+        );
+        assert_eq!(ParseColorErrorKind::UnknownName, kind("light_red"));
+        assert_eq!(ParseColorErrorKind::UnknownName, kind(""));
+        // a function name with a multibyte character is not sliced inside the character
+        assert_eq!(ParseColorErrorKind::UnknownName, kind("rgé(1,2,3)"));
+        assert_eq!(ParseColorErrorKind::UnknownName, kind("hé(1,2,3)"));
+    }
+
+    #[test]
+    fn test_git_names() {
+        assert_eq!(Some(Color::Brown), Color::from_git_name("yellow"));
+        assert_eq!(Some(Color::White), Color::from_git_name("brightwhite"));
+        assert_eq!(Some(Color::DarkGray), Color::from_git_name("BrightBlack"));
+        assert_eq!(Some(Color::Ansi256(208)), Color::from_git_name("208"));
+        assert_eq!(None, Color::from_git_name("bright"));
+        assert_eq!(None, Color::from_git_name("light_red"));
+        for color in Color::NAMED.into_iter().chain([
+            Color::Default,
+            Color::Ansi256(7),
+            Color::Rgb(1, 171, 255),
+        ]) {
+            let name = color.git_name().to_string();
+            assert_eq!(Some(color), Color::from_git_name(&name));
+        }
+    }
+}
@@ -0,0 +1,571 @@
+//! Color space conversions and perceptual nearest color search in the terminal palettes.
+
+// the matrices are kept as published
+#![allow(clippy::excessive_precision)]
+
+use crate::{
+    palette::{xterm_index, XTERM_PALETTE},
+    Color, ColorDepth, Rgb,
+};
+
+/// Hue, saturation and lightness. The hue is in degrees, 0-360, saturation and lightness are
+/// in 0-1.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Hsl {
+    pub h: f32,
+    pub s: f32,
+    pub l: f32,
+}
+
+/// Hue, saturation and value. The hue is in degrees, 0-360, saturation and value are in 0-1.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Hsv {
+    pub h: f32,
+    pub s: f32,
+    pub v: f32,
+}
+
+/// CIE L*a*b* color, with the D65 white point. Lightness is in 0-100.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Lab {
+    pub l: f32,
+    pub a: f32,
+    pub b: f32,
+}
+
+/// OKLab perceptual color. Lightness is in 0-1.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Oklab {
+    pub l: f32,
+    pub a: f32,
+    pub b: f32,
+}
+
+/// Returns the hue in degrees of rgb components in 0-1, with their max, min and chroma.
+fn hue(r: f32, g: f32, b: f32) -> (f32, f32, f32, f32) {
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let chroma = max - min;
+    let h = if chroma == 0.0 {
+        0.0
+    } else if max == r {
+        60.0 * ((g - b) / chroma).rem_euclid(6.0)
+    } else if max == g {
+        60.0 * ((b - r) / chroma + 2.0)
+    } else {
+        60.0 * ((r - g) / chroma + 4.0)
+    };
+    (h, max, min, chroma)
+}
+
+/// Returns the rgb components in 0-1 of a hue, chroma and the value added to every component.
+fn from_hue(h: f32, chroma: f32, m: f32) -> Rgb {
+    let h = h.rem_euclid(360.0) / 60.0;
+    let x = chroma * (1.0 - (h % 2.0 - 1.0).abs());
+    let (r, g, b) = match h as u8 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    (to_u8(r + m), to_u8(g + m), to_u8(b + m))
+}
+
+/// Convert a component in 0-1 to 0-255, clamping it.
+fn to_u8(value: f32) -> u8 {
+    (value * 255.0).round().clamp(0.0, 255.0) as u8
+}
+
+fn to_unit((r, g, b): Rgb) -> (f32, f32, f32) {
+    (
+        f32::from(r) / 255.0,
+        f32::from(g) / 255.0,
+        f32::from(b) / 255.0,
+    )
+}
+
+impl Hsl {
+    pub fn from_rgb(rgb: Rgb) -> Self {
+        let (r, g, b) = to_unit(rgb);
+        let (h, max, min, chroma) = hue(r, g, b);
+        let l = (max + min) / 2.0;
+        let s = if chroma == 0.0 {
+            0.0
+        } else {
+            chroma / (1.0 - (2.0 * l - 1.0).abs())
+        };
+        Hsl { h, s, l }
+    }
+
+    pub fn to_rgb(self) -> Rgb {
+        let chroma = (1.0 - (2.0 * self.l - 1.0).abs()) * self.s;
+        from_hue(self.h, chroma, self.l - chroma / 2.0)
+    }
+}
+
+impl Hsv {
+    pub fn from_rgb(rgb: Rgb) -> Self {
+        let (r, g, b) = to_unit(rgb);
+        let (h, max, _, chroma) = hue(r, g, b);
+        let s = if max == 0.0 { 0.0 } else { chroma / max };
+        Hsv { h, s, v: max }
+    }
+
+    pub fn to_rgb(self) -> Rgb {
+        let chroma = self.v * self.s;
+        from_hue(self.h, chroma, self.v - chroma)
+    }
+}
+
+/// sRGB transfer function, from a component in 0-255 to linear light in 0-1.
+pub(crate) fn to_linear(c: u8) -> f32 {
+    let c = f32::from(c) / 255.0;
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Inverse of `to_linear`.
+pub(crate) fn from_linear(c: f32) -> u8 {
+    let c = if c <= 0.0031308 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    };
+    to_u8(c)
+}
+
+/// D65 white point of CIE XYZ.
+const WHITE: (f32, f32, f32) = (0.95047, 1.0, 1.08883);
+
+impl Lab {
+    pub fn from_rgb((r, g, b): Rgb) -> Self {
+        let (r, g, b) = (to_linear(r), to_linear(g), to_linear(b));
+        let x = 0.4124564 * r + 0.3575761 * g + 0.1804375 * b;
+        let y = 0.2126729 * r + 0.7151522 * g + 0.0721750 * b;
+        let z = 0.0193339 * r + 0.119192 * g + 0.9503041 * b;
+        let f = |t: f32| {
+            if t > 216.0 / 24389.0 {
+                t.cbrt()
+            } else {
+                (24389.0 / 27.0 * t + 16.0) / 116.0
+            }
+        };
+        let (fx, fy, fz) = (f(x / WHITE.0), f(y / WHITE.1), f(z / WHITE.2));
+        Lab {
+            l: 116.0 * fy - 16.0,
+            a: 500.0 * (fx - fy),
+            b: 200.0 * (fy - fz),
+        }
+    }
+
+    pub fn to_rgb(self) -> Rgb {
+        let fy = (self.l + 16.0) / 116.0;
+        let fx = fy + self.a / 500.0;
+        let fz = fy - self.b / 200.0;
+        let f = |t: f32| {
+            if t > 6.0 / 29.0 {
+                t * t * t
+            } else {
+                (116.0 * t - 16.0) * 27.0 / 24389.0
+            }
+        };
+        let (x, y, z) = (f(fx) * WHITE.0, f(fy) * WHITE.1, f(fz) * WHITE.2);
+        let r = 3.2404542 * x - 1.5371385 * y - 0.4985314 * z;
+        let g = -0.969266 * x + 1.8760108 * y + 0.041556 * z;
+        let b = 0.0556434 * x - 0.2040259 * y + 1.0572252 * z;
+        (from_linear(r), from_linear(g), from_linear(b))
+    }
+
+    /// Returns the CIEDE2000 color difference to other.
+    pub fn ciede2000(self, other: Lab) -> f32 {
+        let (l1, a1, b1) = (self.l, self.a, self.b);
+        let (l2, a2, b2) = (other.l, other.a, other.b);
+        let c_mean = ((a1.hypot(b1) + a2.hypot(b2)) / 2.0).powi(7);
+        let g = 0.5 * (1.0 - (c_mean / (c_mean + 25f32.powi(7))).sqrt());
+        let (a1, a2) = (a1 * (1.0 + g), a2 * (1.0 + g));
+        let (c1, c2) = (a1.hypot(b1), a2.hypot(b2));
+        let hue = |b: f32, a: f32| {
+            if a == 0.0 && b == 0.0 {
+                0.0
+            } else {
+                b.atan2(a).to_degrees().rem_euclid(360.0)
+            }
+        };
+        let (h1, h2) = (hue(b1, a1), hue(b2, a2));
+
+        let dl = l2 - l1;
+        let dc = c2 - c1;
+        let dh = if c1 * c2 == 0.0 {
+            0.0
+        } else if (h2 - h1).abs() <= 180.0 {
+            h2 - h1
+        } else if h2 - h1 > 180.0 {
+            h2 - h1 - 360.0
+        } else {
+            h2 - h1 + 360.0
+        };
+        let dh = 2.0 * (c1 * c2).sqrt() * (dh / 2.0).to_radians().sin();
+
+        let l_mean = (l1 + l2) / 2.0;
+        let c_mean = (c1 + c2) / 2.0;
+        let h_mean = if c1 * c2 == 0.0 {
+            h1 + h2
+        } else if (h1 - h2).abs() <= 180.0 {
+            (h1 + h2) / 2.0
+        } else if h1 + h2 < 360.0 {
+            (h1 + h2 + 360.0) / 2.0
+        } else {
+            (h1 + h2 - 360.0) / 2.0
+        };
+        let t = 1.0 - 0.17 * (h_mean - 30.0).to_radians().cos()
+            + 0.24 * (2.0 * h_mean).to_radians().cos()
+            + 0.32 * (3.0 * h_mean + 6.0).to_radians().cos()
+            - 0.20 * (4.0 * h_mean - 63.0).to_radians().cos();
+        let l_offset = (l_mean - 50.0).powi(2);
+        let sl = 1.0 + 0.015 * l_offset / (20.0 + l_offset).sqrt();
+        let sc = 1.0 + 0.045 * c_mean;
+        let sh = 1.0 + 0.015 * c_mean * t;
+        let c_mean = c_mean.powi(7);
+        let rt = -2.0
+            * (c_mean / (c_mean + 25f32.powi(7))).sqrt()
+            * (60.0 * (-((h_mean - 275.0) / 25.0).powi(2)).exp())
+                .to_radians()
+                .sin();
+        let (dl, dc, dh) = (dl / sl, dc / sc, dh / sh);
+        (dl * dl + dc * dc + dh * dh + rt * dc * dh).sqrt()
+    }
+}
+
+impl Oklab {
+    pub fn from_rgb((r, g, b): Rgb) -> Self {
+        let (r, g, b) = (to_linear(r), to_linear(g), to_linear(b));
+        let l = (0.4122214708 * r + 0.5363325363 * g + 0.0514459929 * b).cbrt();
+        let m = (0.2119034982 * r + 0.6806995451 * g + 0.1073969566 * b).cbrt();
+        let s = (0.0883024619 * r + 0.2817188376 * g + 0.6299787005 * b).cbrt();
+        Oklab {
+            l: 0.2104542553 * l + 0.793617785 * m - 0.0040720468 * s,
+            a: 1.9779984951 * l - 2.428592205 * m + 0.4505937099 * s,
+            b: 0.0259040371 * l + 0.7827717662 * m - 0.808675766 * s,
+        }
+    }
+
+    pub fn to_rgb(self) -> Rgb {
+        let l = (self.l + 0.3963377774 * self.a + 0.2158037573 * self.b).powi(3);
+        let m = (self.l - 0.1055613458 * self.a - 0.0638541728 * self.b).powi(3);
+        let s = (self.l - 0.0894841775 * self.a - 1.291485548 * self.b).powi(3);
+        (
+            from_linear(4.0767416621 * l - 3.3077115913 * m + 0.2309699292 * s),
+            from_linear(-1.2684380046 * l + 2.6097574011 * m - 0.3413193965 * s),
+            from_linear(-0.0041960863 * l - 0.7034186147 * m + 1.707614701 * s),
+        )
+    }
+
+    /// Returns the euclidean distance to other.
+    pub fn distance(self, other: Oklab) -> f32 {
+        let (dl, da, db) = (self.l - other.l, self.a - other.a, self.b - other.b);
+        (dl * dl + da * da + db * db).sqrt()
+    }
+}
+
+/// How the distance between two colors is measured when looking for the nearest color.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DistanceMetric {
+    /// Euclidean distance of the sRGB components. Fast, but far from perception.
+    Rgb,
+    /// CIEDE2000 difference of the CIE L*a*b* colors. The most accurate, and the slowest.
+    Ciede2000,
+    /// Euclidean distance of the OKLab colors. Close to CIEDE2000, and much faster.
+    #[default]
+    Oklab,
+}
+
+/// A color converted to the space of a metric, to compare it with many others.
+#[derive(Clone, Copy)]
+enum Point {
+    Rgb(f32, f32, f32),
+    Lab(Lab),
+    Oklab(Oklab),
+}
+
+impl DistanceMetric {
+    fn point(self, rgb: Rgb) -> Point {
+        match self {
+            DistanceMetric::Rgb => {
+                let (r, g, b) = rgb;
+                Point::Rgb(r.into(), g.into(), b.into())
+            }
+            DistanceMetric::Ciede2000 => Point::Lab(Lab::from_rgb(rgb)),
+            DistanceMetric::Oklab => Point::Oklab(Oklab::from_rgb(rgb)),
+        }
+    }
+
+    /// Returns the distance between two colors.
+    pub fn distance(self, a: Rgb, b: Rgb) -> f32 {
+        distance(self.point(a), self.point(b))
+    }
+}
+
+fn distance(a: Point, b: Point) -> f32 {
+    match (a, b) {
+        (Point::Rgb(r1, g1, b1), Point::Rgb(r2, g2, b2)) => {
+            ((r1 - r2).powi(2) + (g1 - g2).powi(2) + (b1 - b2).powi(2)).sqrt()
+        }
+        (Point::Lab(a), Point::Lab(b)) => a.ciede2000(b),
+        (Point::Oklab(a), Point::Oklab(b)) => a.distance(b),
+        _ => f32::MAX,
+    }
+}
+
+/// Returns the index of the nearest color among points, which are indexes of the palette.
+fn nearest(target: Point, points: impl Iterator<Item = (u8, Point)>) -> u8 {
+    let mut best = (0, f32::MAX);
+    for (index, point) in points {
+        let d = distance(target, point);
+        if d < best.1 {
+            best = (index, d);
+        }
+    }
+    best.0
+}
+
+/// Returns the nearest color of the 256 color palette. Only the color cube and the gray ramp,
+/// indexes 16-255, are searched, as the first 16 colors change with the terminal theme.
+///
+/// # Examples
+///
+/// ```
+/// use ansistream::{nearest_ansi256, DistanceMetric};
+///
+/// assert_eq!(208, nearest_ansi256((255, 135, 0), DistanceMetric::Oklab));
+/// assert_eq!(236, nearest_ansi256((48, 48, 48), DistanceMetric::Ciede2000));
+/// ```
+pub fn nearest_ansi256(rgb: Rgb, metric: DistanceMetric) -> u8 {
+    let target = metric.point(rgb);
+    nearest(
+        target,
+        (16..=255).map(|i| (i, metric.point(XTERM_PALETTE[usize::from(i)]))),
+    )
+}
+
+/// Returns the nearest of the 16 named colors, using the default xterm values.
+pub fn nearest_ansi16(rgb: Rgb, metric: DistanceMetric) -> Color {
+    let target = metric.point(rgb);
+    let index = nearest(
+        target,
+        (0..16).map(|i| (i, metric.point(XTERM_PALETTE[usize::from(i)]))),
+    );
+    Color::NAMED[usize::from(index)]
+}
+
+/// Returns the color nearest to rgb which a terminal of depth can show.
+pub(crate) fn downgrade(rgb: Rgb, depth: ColorDepth, cache: &mut Option<NearestCache>) -> Color {
+    match depth {
+        ColorDepth::TrueColor => Color::Rgb(rgb.0, rgb.1, rgb.2),
+        ColorDepth::Ansi256 => {
+            let cache = cache.get_or_insert_with(|| NearestCache::new(Default::default()));
+            Color::Ansi256(cache.ansi256(rgb))
+        }
+        ColorDepth::Ansi16 => nearest_ansi16(rgb, Default::default()),
+    }
+}
+
+/// Bits kept of each RGB component by `NearestCache`.
+const CACHE_BITS: u32 = 6;
+
+/// Nearest 256 palette colors of truecolor values, computed once per color. The palette is
+/// converted to the metric's space when the cache is created, and RGB values are quantized to
+/// 6 bits per component, so the cache holds at most 262144 entries, about 512 KiB. Colors of
+/// the palette give their own index, other colors the nearest color of the center of their
+/// bucket, which may differ from `nearest_ansi256` near the edge between two colors.
+///
+/// # Examples
+///
+/// ```
+/// use ansistream::{DistanceMetric, NearestCache};
+///
+/// let mut cache = NearestCache::new(DistanceMetric::Ciede2000);
+/// for x in 0..=255 {
+///     let index = cache.ansi256((x, 128, 255 - x));
+///     assert!(index >= 16);
+/// }
+/// ```
+pub struct NearestCache {
+    metric: DistanceMetric,
+    palette: Vec<Point>,
+    table: Vec<u16>,
+}
+
+impl NearestCache {
+    /// Marks an entry which was not computed yet.
+    const EMPTY: u16 = u16::MAX;
+
+    /// Initializes an empty cache for metric. The table of about 512 KiB is allocated at once,
+    /// so a cache is meant to be created once and reused.
+    pub fn new(metric: DistanceMetric) -> Self {
+        Self {
+            metric,
+            palette: (16..=255).map(|i| metric.point(XTERM_PALETTE[i])).collect(),
+            table: vec![Self::EMPTY; 1 << (3 * CACHE_BITS)],
+        }
+    }
+
+    /// Returns the metric of this cache.
+    pub fn metric(&self) -> DistanceMetric {
+        self.metric
+    }
+
+    /// Returns the nearest color of the 256 color palette, like `nearest_ansi256`.
+    pub fn ansi256(&mut self, (r, g, b): Rgb) -> u8 {
+        if let Some(index @ 16..) = xterm_index((r, g, b)) {
+            return index;
+        }
+        let shift = 8 - CACHE_BITS;
+        let key = (usize::from(r >> shift) << (2 * CACHE_BITS))
+            | (usize::from(g >> shift) << CACHE_BITS)
+            | usize::from(b >> shift);
+        if self.table[key] == Self::EMPTY {
+            // the center of the quantized bucket
+            let center = |c: u8| (c >> shift << shift) | (1 << (shift - 1));
+            let target = self.metric.point((center(r), center(g), center(b)));
+            let points = self.palette.iter().zip(16..=255).map(|(p, i)| (i, *p));
+            self.table[key] = nearest(target, points).into();
+        }
+        self.table[key] as u8
+    }
+}
+
+impl core::fmt::Debug for NearestCache {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("NearestCache")
+            .field("metric", &self.metric)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(expected: f32, value: f32, tolerance: f32) {
+        assert!(
+            (expected - value).abs() <= tolerance,
+            "expected {expected}, got {value}"
+        );
+    }
+
+    #[test]
+    fn test_hsl_hsv() {
+        let hsl = Hsl::from_rgb((255, 128, 0));
+        assert_close(30.1, hsl.h, 0.1);
+        assert_close(1.0, hsl.s, 0.001);
+        assert_close(0.5, hsl.l, 0.001);
+        let hsv = Hsv::from_rgb((0, 0, 128));
+        assert_close(240.0, hsv.h, 0.001);
+        assert_close(0.502, hsv.v, 0.001);
+        for rgb in [(0, 0, 0), (255, 255, 255), (12, 200, 99), (255, 0, 128)] {
+            assert_eq!(rgb, Hsl::from_rgb(rgb).to_rgb());
+            assert_eq!(rgb, Hsv::from_rgb(rgb).to_rgb());
+        }
+    }
+
+    #[test]
+    fn test_lab_oklab() {
+        let lab = Lab::from_rgb((255, 0, 0));
+        assert_close(53.24, lab.l, 0.01);
+        assert_close(80.09, lab.a, 0.01);
+        assert_close(67.20, lab.b, 0.01);
+        let oklab = Oklab::from_rgb((255, 0, 0));
+        assert_close(0.628, oklab.l, 0.001);
+        assert_close(0.2249, oklab.a, 0.001);
+        assert_close(0.1258, oklab.b, 0.001);
+        for rgb in [(0, 0, 0), (255, 255, 255), (12, 200, 99), (255, 0, 128)] {
+            assert_eq!(rgb, Lab::from_rgb(rgb).to_rgb());
+            assert_eq!(rgb, Oklab::from_rgb(rgb).to_rgb());
+        }
+    }
+
+    #[test]
+    fn test_ciede2000() {
+        // reference pairs of Sharma, Wu and Dalal
+        let pairs = [
+            ((50.0, 2.6772, -79.7751), (50.0, 0.0, -82.7485), 2.0425),
+            ((50.0, 2.5, 0.0), (73.0, 25.0, -18.0), 27.1492),
+            ((2.0776, 0.0795, -1.135), (0.9033, -0.0636, -0.5514), 0.9082),
+        ];
+        for ((l1, a1, b1), (l2, a2, b2), expected) in pairs {
+            let a = Lab {
+                l: l1,
+                a: a1,
+                b: b1,
+            };
+            let b = Lab {
+                l: l2,
+                a: a2,
+                b: b2,
+            };
+            assert_close(expected, a.ciede2000(b), 0.001);
+            assert_close(expected, b.ciede2000(a), 0.001);
+        }
+    }
+
+    #[test]
+    fn test_nearest() {
+        for metric in [
+            DistanceMetric::Rgb,
+            DistanceMetric::Ciede2000,
+            DistanceMetric::Oklab,
+        ] {
+            // exact palette colors are found
+            for index in [16, 46, 208, 231, 244] {
+                assert_eq!(
+                    index,
+                    nearest_ansi256(XTERM_PALETTE[usize::from(index)], metric)
+                );
+            }
+            assert_eq!(Color::Red, nearest_ansi16((200, 10, 10), metric));
+        }
+        // a dark blue is closer to blue than to black for the eye
+        assert_eq!(
+            Color::Blue,
+            nearest_ansi16((0, 0, 110), DistanceMetric::Oklab)
+        );
+    }
+
+    #[test]
+    fn test_cache() {
+        let mut cache = NearestCache::new(DistanceMetric::Oklab);
+        // components which are bucket centers are not changed by the quantization
+        for rgb in [(254, 134, 2), (2, 2, 2), (94, 134, 174), (130, 130, 130)] {
+            assert_eq!(nearest_ansi256(rgb, cache.metric()), cache.ansi256(rgb));
+            assert_eq!(cache.ansi256(rgb), cache.ansi256(rgb));
+        }
+        // other colors get a close match
+        for rgb in [(255, 135, 0), (95, 135, 175), (10, 200, 99)] {
+            let exact = XTERM_PALETTE[usize::from(nearest_ansi256(rgb, cache.metric()))];
+            let cached = XTERM_PALETTE[usize::from(cache.ansi256(rgb))];
+            assert!(cache.metric().distance(exact, cached) < 0.05);
+        }
+    }
+
+    #[test]
+    fn test_cache_palette() {
+        for metric in [
+            DistanceMetric::Rgb,
+            DistanceMetric::Ciede2000,
+            DistanceMetric::Oklab,
+        ] {
+            let mut cache = NearestCache::new(metric);
+            for (i, &rgb) in XTERM_PALETTE.iter().enumerate().skip(16) {
+                assert_eq!(i, usize::from(cache.ansi256(rgb)), "{metric:?}");
+            }
+            // named colors which are also in the cube give its index
+            assert_eq!(16, cache.ansi256(XTERM_PALETTE[0]));
+            assert_eq!(231, cache.ansi256(XTERM_PALETTE[15]));
+        }
+    }
+}
@@ -0,0 +1,193 @@
+//! WCAG contrast ratios, and foreground colors adjusted to reach them.
+
+use crate::{colorspace::to_linear, Color, ColorDepth, ColorScheme, Oklab, Rgb, XTERM_PALETTE};
+
+/// The minimum contrast ratio of WCAG level AA for normal text.
+pub const WCAG_AA: f32 = 4.5;
+
+/// The minimum contrast ratio of WCAG level AA for large or bold text.
+pub const WCAG_AA_LARGE: f32 = 3.0;
+
+/// The minimum contrast ratio of WCAG level AAA for normal text.
+pub const WCAG_AAA: f32 = 7.0;
+
+/// Steps of the search of the lightness which reaches a contrast ratio.
+const SEARCH_STEPS: usize = 16;
+
+/// Returns the WCAG relative luminance of rgb, from 0 for black to 1 for white.
+pub fn relative_luminance((r, g, b): Rgb) -> f32 {
+    0.2126 * to_linear(r) + 0.7152 * to_linear(g) + 0.0722 * to_linear(b)
+}
+
+/// Returns the WCAG contrast ratio of two colors, from 1 for the same luminance to 21 for
+/// black and white. The order of the colors does not matter.
+///
+/// # Examples
+///
+/// ```
+/// use ansistream::{contrast_ratio, WCAG_AA};
+///
+/// assert_eq!(21.0, contrast_ratio((0, 0, 0), (255, 255, 255)).round());
+/// assert!(contrast_ratio((119, 119, 119), (255, 255, 255)) < WCAG_AA);
+/// ```
+pub fn contrast_ratio(a: Rgb, b: Rgb) -> f32 {
+    let (a, b) = (relative_luminance(a), relative_luminance(b));
+    (a.max(b) + 0.05) / (a.min(b) + 0.05)
+}
+
+/// Returns fg with the OKLab lightness changed as little as needed to reach min_ratio against
+/// bg. The lightness goes up or down, whichever is the smaller change, and the hue is kept
+/// as far as the gamut allows. If neither way reaches min_ratio, black or white is returned,
+/// whichever has more contrast.
+///
+/// # Examples
+///
+/// ```
+/// use ansistream::{contrast_ratio, ensure_contrast, WCAG_AA};
+///
+/// let bg = (40, 40, 40);
+/// let fg = ensure_contrast((100, 60, 160), bg, WCAG_AA);
+/// assert!(contrast_ratio(fg, bg) >= WCAG_AA);
+/// assert_eq!((220, 220, 220), ensure_contrast((220, 220, 220), bg, WCAG_AA));
+/// ```
+pub fn ensure_contrast(fg: Rgb, bg: Rgb, min_ratio: f32) -> Rgb {
+    if contrast_ratio(fg, bg) >= min_ratio {
+        return fg;
+    }
+    let lab = Oklab::from_rgb(fg);
+    // each way, the smallest change which reaches min_ratio
+    let search = |end: f32, extreme: Rgb| {
+        if contrast_ratio(extreme, bg) < min_ratio {
+            return None;
+        }
+        let at = |t: f32| {
+            if t >= 1.0 {
+                extreme
+            } else {
+                let l = lab.l + (end - lab.l) * t;
+                Oklab { l, ..lab }.to_rgb()
+            }
+        };
+        let (mut low, mut high) = (0.0, 1.0);
+        for _ in 0..SEARCH_STEPS {
+            let mid = (low + high) / 2.0;
+            if contrast_ratio(at(mid), bg) >= min_ratio {
+                high = mid;
+            } else {
+                low = mid;
+            }
+        }
+        Some(((end - lab.l).abs() * high, at(high)))
+    };
+    let (black, white) = ((0, 0, 0), (255, 255, 255));
+    match (search(1.0, white), search(0.0, black)) {
+        (Some(lighter), Some(darker)) if darker.0 < lighter.0 => darker.1,
+        (Some((_, lighter)), _) => lighter,
+        (None, Some((_, darker))) => darker,
+        (None, None) if contrast_ratio(white, bg) > contrast_ratio(black, bg) => white,
+        (None, None) => black,
+    }
+}
+
+/// Returns the color which replaces the foreground fg on bg to reach min_ratio, or `None` if
+/// fg has enough contrast. On a palette depth it is the color of the palette nearest to the
+/// adjusted fg which reaches min_ratio, the RGB values of named colors coming from scheme, or
+/// the color with the most contrast if none does.
+pub(crate) fn contrast_color(
+    fg: Color,
+    bg: Color,
+    min_ratio: f32,
+    scheme: &ColorScheme,
+    depth: ColorDepth,
+) -> Option<Color> {
+    let (fg, bg) = (scheme.resolve_fg(fg), scheme.resolve_bg(bg));
+    if contrast_ratio(fg, bg) >= min_ratio {
+        return None;
+    }
+    let adjusted = ensure_contrast(fg, bg, min_ratio);
+    let target = Oklab::from_rgb(adjusted);
+    // the color nearest to adjusted which reaches min_ratio, or else the one with the most
+    // contrast
+    let best = |colors: &mut dyn Iterator<Item = (Color, Rgb)>| {
+        colors
+            .map(|(color, rgb)| {
+                let ratio = contrast_ratio(rgb, bg);
+                if ratio >= min_ratio {
+                    (color, false, target.distance(Oklab::from_rgb(rgb)))
+                } else {
+                    (color, true, -ratio)
+                }
+            })
+            .min_by(|a, b| a.1.cmp(&b.1).then(a.2.total_cmp(&b.2)))
+            .map(|(color, _, _)| color)
+    };
+    let mut named = Color::NAMED
+        .iter()
+        .map(|&color| (color, scheme.resolve_fg(color)));
+    match depth {
+        ColorDepth::TrueColor => Some(Color::Rgb(adjusted.0, adjusted.1, adjusted.2)),
+        ColorDepth::Ansi256 => best(
+            &mut named
+                .chain((16..=255).map(|i| (Color::Ansi256(i), XTERM_PALETTE[usize::from(i)]))),
+        ),
+        ColorDepth::Ansi16 => best(&mut named),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_contrast_ratio() {
+        assert_eq!(1.0, contrast_ratio((10, 20, 30), (10, 20, 30)));
+        assert!((contrast_ratio((255, 255, 255), (0, 0, 0)) - 21.0).abs() < 1e-4);
+        // the gray of WCAG examples, just below AA on white
+        let ratio = contrast_ratio((119, 119, 119), (255, 255, 255));
+        assert!((ratio - 4.48).abs() < 0.01);
+        assert!((relative_luminance((255, 0, 0)) - 0.2126).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_ensure_contrast() {
+        // a dark blue on black gets lighter, a light yellow on white gets darker
+        for (fg, bg) in [((0, 0, 139), (0, 0, 0)), ((255, 255, 150), (255, 255, 255))] {
+            let adjusted = ensure_contrast(fg, bg, WCAG_AA);
+            assert!(contrast_ratio(adjusted, bg) >= WCAG_AA);
+            assert!(contrast_ratio(adjusted, bg) < WCAG_AA + 0.2);
+        }
+        let lighter = ensure_contrast((0, 0, 139), (0, 0, 0), WCAG_AA);
+        assert!(lighter.2 > lighter.0);
+        // middle gray can not reach 21, the best is returned
+        assert_eq!(
+            (0, 0, 0),
+            ensure_contrast((128, 128, 128), (255, 255, 255), 21.0)
+        );
+        assert_eq!(
+            (255, 255, 255),
+            ensure_contrast((128, 128, 128), (100, 100, 100), 21.0)
+        );
+    }
+
+    #[test]
+    fn test_contrast_color() {
+        let scheme = ColorScheme::default();
+        let color = |fg, bg, depth| contrast_color(fg, bg, WCAG_AA_LARGE, &scheme, depth);
+        assert_eq!(None, color(Color::White, Color::Black, ColorDepth::Ansi16));
+        // blue on black is too dark, light blue is the nearest named color with enough contrast
+        assert_eq!(
+            Some(Color::LightBlue),
+            color(Color::Blue, Color::Default, ColorDepth::Ansi16)
+        );
+        let Some(Color::Ansi256(index)) = color(Color::Blue, Color::Black, ColorDepth::Ansi256)
+        else {
+            panic!("expected a palette color");
+        };
+        assert!(contrast_ratio(XTERM_PALETTE[usize::from(index)], (0, 0, 0)) >= WCAG_AA_LARGE);
+        let Some(Color::Rgb(r, g, b)) = color(Color::Blue, Color::Black, ColorDepth::TrueColor)
+        else {
+            panic!("expected a RGB color");
+        };
+        assert!(contrast_ratio((r, g, b), (0, 0, 0)) >= WCAG_AA_LARGE);
+    }
+}
@@ -0,0 +1,189 @@
+//! Simulation of color vision deficiencies.
+
+// the matrices are kept as published
+#![allow(clippy::excessive_precision)]
+
+use crate::{
+    colorspace::{downgrade, from_linear, to_linear, NearestCache},
+    Color, ColorDepth, ColorScheme, Rgb,
+};
+
+/// A color vision deficiency, where one kind of cone of the eye is missing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Deficiency {
+    /// No red cones, red and green are confused and red looks darker.
+    Protanopia,
+    /// No green cones, the most common deficiency, red and green are confused.
+    Deuteranopia,
+    /// No blue cones, blue and green, and yellow and violet are confused.
+    Tritanopia,
+}
+
+impl Deficiency {
+    /// The deficiencies.
+    pub const ALL: [Deficiency; 3] = [
+        Deficiency::Protanopia,
+        Deficiency::Deuteranopia,
+        Deficiency::Tritanopia,
+    ];
+
+    /// Returns how rgb looks with the deficiency, with the model of Machado, Oliveira and
+    /// Fernandes (2009).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ansistream::{Deficiency, Hsl};
+    ///
+    /// // red and green get the same hue, only their lightness differs
+    /// let red = Hsl::from_rgb(Deficiency::Deuteranopia.simulate((205, 0, 0)));
+    /// let green = Hsl::from_rgb(Deficiency::Deuteranopia.simulate((0, 205, 0)));
+    /// assert!((red.h - green.h).abs() < 5.0);
+    /// ```
+    pub fn simulate(self, rgb: Rgb) -> Rgb {
+        self.simulate_severity(rgb, 1.0)
+    }
+
+    /// Returns how rgb looks with an anomalous trichromacy of severity, from 0 for normal
+    /// vision to 1 for the full deficiency. Severity is clamped to 0-1.
+    pub fn simulate_severity(self, (r, g, b): Rgb, severity: f32) -> Rgb {
+        let severity = severity.clamp(0.0, 1.0);
+        let linear = [to_linear(r), to_linear(g), to_linear(b)];
+        let row = |i: usize| {
+            let m = self.matrix()[i];
+            let simulated = m[0] * linear[0] + m[1] * linear[1] + m[2] * linear[2];
+            from_linear(linear[i] + (simulated - linear[i]) * severity)
+        };
+        (row(0), row(1), row(2))
+    }
+
+    /// Returns the name of the deficiency, like `deuteranopia`.
+    pub const fn name(self) -> &'static str {
+        match self {
+            Deficiency::Protanopia => "protanopia",
+            Deficiency::Deuteranopia => "deuteranopia",
+            Deficiency::Tritanopia => "tritanopia",
+        }
+    }
+
+    /// Returns the deficiency of a name, ignoring case.
+    pub fn from_name(name: &str) -> Option<Deficiency> {
+        Deficiency::ALL
+            .into_iter()
+            .find(|d| d.name().eq_ignore_ascii_case(name))
+    }
+
+    /// The simulation matrix of linear RGB values, for severity 1.
+    const fn matrix(self) -> [[f32; 3]; 3] {
+        match self {
+            Deficiency::Protanopia => [
+                [0.152286, 1.052583, -0.204868],
+                [0.114503, 0.786281, 0.099216],
+                [-0.003882, -0.048116, 1.051998],
+            ],
+            Deficiency::Deuteranopia => [
+                [0.367322, 0.860646, -0.227968],
+                [0.280085, 0.672501, 0.047413],
+                [-0.011820, 0.042940, 0.968881],
+            ],
+            Deficiency::Tritanopia => [
+                [1.255528, -0.076749, -0.178779],
+                [-0.078411, 0.930809, 0.147602],
+                [0.004733, 0.691367, 0.303900],
+            ],
+        }
+    }
+}
+
+/// Returns how color looks with deficiency, as the nearest color a terminal of depth can show.
+/// Named colors have the RGB values of scheme, and the default color is kept.
+pub(crate) fn simulate_color(
+    color: Color,
+    deficiency: Deficiency,
+    scheme: &ColorScheme,
+    depth: ColorDepth,
+    cache: &mut Option<NearestCache>,
+) -> Color {
+    match color {
+        Color::Default => Color::Default,
+        color => downgrade(deficiency.simulate(scheme.resolve_fg(color)), depth, cache),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_simulate() {
+        // grays are not changed
+        for deficiency in Deficiency::ALL {
+            for gray in [(0, 0, 0), (128, 128, 128), (255, 255, 255)] {
+                let simulated = deficiency.simulate(gray);
+                assert!(simulated.0.abs_diff(gray.0) <= 1, "{deficiency:?} {gray:?}");
+                assert!(simulated.2.abs_diff(gray.2) <= 1, "{deficiency:?} {gray:?}");
+            }
+        }
+        // protanopes see red darker
+        assert!(Deficiency::Protanopia.simulate((255, 0, 0)).0 < 150);
+        // tritanopes confuse blue and green more than red and green
+        let (blue, green) = ((0, 0, 255), (0, 160, 0));
+        let t = |rgb| Deficiency::Tritanopia.simulate(rgb);
+        assert!(t(blue).2.abs_diff(t(green).2) < blue.2.abs_diff(green.2));
+        assert_eq!(
+            (200, 30, 90),
+            Deficiency::Deuteranopia.simulate_severity((200, 30, 90), 0.0)
+        );
+    }
+
+    #[test]
+    fn test_cvd_safe_theme() {
+        use crate::{Oklab, Role, Theme};
+
+        // the status colors stay distinct with every deficiency
+        let roles = [Role::Error, Role::Warning, Role::Info, Role::Success];
+        for deficiency in Deficiency::ALL {
+            let seen: Vec<Oklab> = roles
+                .iter()
+                .map(|&role| match Theme::CVD_SAFE.style(role).foreground {
+                    Some(Color::Rgb(r, g, b)) => Oklab::from_rgb(deficiency.simulate((r, g, b))),
+                    _ => panic!("expected a RGB color"),
+                })
+                .collect();
+            for (i, a) in seen.iter().enumerate() {
+                for b in &seen[i + 1..] {
+                    assert!(a.distance(*b) > 0.05, "{deficiency:?}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_names() {
+        for deficiency in Deficiency::ALL {
+            assert_eq!(Some(deficiency), Deficiency::from_name(deficiency.name()));
+        }
+        assert_eq!(
+            Some(Deficiency::Tritanopia),
+            Deficiency::from_name("Tritanopia")
+        );
+        assert_eq!(None, Deficiency::from_name("achromatopsia"));
+    }
+
+    #[test]
+    fn test_simulate_color() {
+        let scheme = ColorScheme::default();
+        let mut cache = None;
+        let mut color = |color, depth| {
+            simulate_color(color, Deficiency::Deuteranopia, &scheme, depth, &mut cache)
+        };
+        assert_eq!(Color::Default, color(Color::Default, ColorDepth::Ansi16));
+        let Color::Rgb(r, g, b) = color(Color::Red, ColorDepth::TrueColor) else {
+            panic!("expected a RGB color");
+        };
+        assert_eq!(Deficiency::Deuteranopia.simulate((205, 0, 0)), (r, g, b));
+        // red loses its hue and looks like dark gray, green looks like brown
+        assert_eq!(Color::DarkGray, color(Color::Red, ColorDepth::Ansi16));
+        assert_eq!(Color::Brown, color(Color::Green, ColorDepth::Ansi16));
+    }
+}
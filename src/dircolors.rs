@@ -0,0 +1,440 @@
+//! Styles of file names from `LS_COLORS` and dircolors databases.
+
+use std::{
+    env,
+    fs::{self, Metadata},
+    path::Path,
+};
+
+use crate::{ParseStyleError, Style};
+
+/// The LS_COLORS built-in defaults of GNU ls, without doors, which only Solaris has.
+const GNU_DEFAULTS: &str = "di=01;34:ln=01;36:pi=40;33:so=01;35:bd=40;33;01:\
+    cd=40;33;01:or=40;31;01:su=37;41:sg=30;43:tw=30;42:ow=34;42:st=37;44:ex=01;32";
+
+/// The kinds of files with a style, in the order of `KEYS`.
+#[derive(Debug, Clone, Copy)]
+enum Kind {
+    Normal,
+    File,
+    Dir,
+    Link,
+    MultiHardLink,
+    Fifo,
+    Socket,
+    BlockDevice,
+    CharDevice,
+    Orphan,
+    Missing,
+    Setuid,
+    Setgid,
+    StickyOtherWritable,
+    OtherWritable,
+    Sticky,
+    Exec,
+}
+
+/// The LS_COLORS key and the dircolors keywords of each kind.
+const KEYS: [(&str, &[&str]); 17] = [
+    ("no", &["NORMAL", "NORM"]),
+    ("fi", &["FILE"]),
+    ("di", &["DIR"]),
+    ("ln", &["LINK", "LNK", "SYMLINK"]),
+    ("mh", &["MULTIHARDLINK"]),
+    ("pi", &["FIFO", "PIPE"]),
+    ("so", &["SOCK"]),
+    ("bd", &["BLK", "BLOCK"]),
+    ("cd", &["CHR", "CHAR"]),
+    ("or", &["ORPHAN"]),
+    ("mi", &["MISSING"]),
+    ("su", &["SETUID"]),
+    ("sg", &["SETGID"]),
+    ("tw", &["STICKY_OTHER_WRITABLE"]),
+    ("ow", &["OTHER_WRITABLE"]),
+    ("st", &["STICKY"]),
+    ("ex", &["EXEC"]),
+];
+
+/// The styles `ls` gives to file names, from the `LS_COLORS` environment variable or from a
+/// dircolors database. A name is styled by the kind of file, like `di` for directories and
+/// `ex` for executables, or by a pattern of its end, like `*.tar`.
+///
+/// Entries override the built-in defaults of GNU ls, which `LsColors::default()` returns.
+/// Unknown keys and terminal codes like `lc` are ignored, and an empty style like `00` unsets
+/// an entry. A symbolic link with `ln=target` is styled like its target.
+///
+/// # Examples
+///
+/// ```
+/// use ansistream::{Color, LsColors, Style};
+///
+/// let colors = LsColors::parse("di=01;34:*.rs=38;5;208").unwrap();
+/// assert_eq!(Some(Style::new().fg(Color::Ansi256(208))), colors.get("*.rs"));
+/// assert_eq!(Some(Style::new().bold().fg(Color::Blue)), colors.get("di"));
+/// assert_eq!(6, LsColors::parse("di=01;x").unwrap_err().position());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LsColors {
+    kinds: [Option<Style>; KEYS.len()],
+    /// The ends of names, like `.tar` for `*.tar`, in the order they were defined.
+    patterns: Vec<(String, Style)>,
+    link_target: bool,
+}
+
+impl Default for LsColors {
+    fn default() -> Self {
+        let mut colors = Self {
+            kinds: [None; KEYS.len()],
+            patterns: Vec::new(),
+            link_target: false,
+        };
+        for entry in GNU_DEFAULTS.split(':') {
+            if let Some((key, value)) = entry.split_once('=') {
+                colors.set_key(key, Style::from_sgr(value).unwrap_or_default());
+            }
+        }
+        colors
+    }
+}
+
+impl LsColors {
+    /// Parse the value of `LS_COLORS`, like `di=01;34:*.tar=01;31`. Errors have the byte
+    /// position of the problem in ls_colors.
+    pub fn parse(ls_colors: &str) -> Result<LsColors, ParseStyleError> {
+        let mut colors = Self::default();
+        let mut position = 0;
+        for entry in ls_colors.split(':') {
+            let start = position;
+            position += entry.len() + 1;
+            let Some((key, value)) = entry.split_once('=') else {
+                continue;
+            };
+            if key == "ln" && value == "target" {
+                colors.link_target = true;
+                continue;
+            }
+            let style = Style::from_sgr(value).map_err(|e| e.offset(start + key.len() + 1))?;
+            match key.strip_prefix('*') {
+                Some(pattern) => colors.set_pattern(pattern, style),
+                None => colors.set_key(key, style),
+            }
+        }
+        Ok(colors)
+    }
+
+    /// Parse the `LS_COLORS` environment variable, or returns the defaults if it is not set.
+    pub fn from_env() -> Result<LsColors, ParseStyleError> {
+        match env::var("LS_COLORS") {
+            Ok(ls_colors) => Self::parse(&ls_colors),
+            Err(_) => Ok(Self::default()),
+        }
+    }
+
+    /// Parse a database of the `dircolors` command, with lines like `DIR 01;34` and
+    /// `.tar 01;31`. Keywords are not case sensitive, `TERM` sections and other settings are
+    /// ignored, and `#` starts a comment. Errors have the byte position of the problem in db.
+    pub fn from_dircolors(db: &str) -> Result<LsColors, ParseStyleError> {
+        let mut colors = Self::default();
+        for line in db.lines() {
+            let line = match line.find('#') {
+                Some(0) => continue,
+                Some(i) if line[..i].ends_with(char::is_whitespace) => &line[..i],
+                _ => line,
+            };
+            let Some((keyword, value)) = line.trim().split_once(char::is_whitespace) else {
+                continue;
+            };
+            let value = value.trim();
+            if keyword.eq_ignore_ascii_case("LINK") && value == "target" {
+                colors.link_target = true;
+                continue;
+            }
+            let parse = || {
+                let start = value.as_ptr() as usize - db.as_ptr() as usize;
+                Style::from_sgr(value).map_err(|e| e.offset(start))
+            };
+            if let Some(pattern) = keyword.strip_prefix('*') {
+                colors.set_pattern(pattern, parse()?);
+            } else if keyword.starts_with('.') {
+                colors.set_pattern(keyword, parse()?);
+            } else if let Some(i) = KEYS
+                .iter()
+                .position(|(_, keywords)| keywords.iter().any(|k| k.eq_ignore_ascii_case(keyword)))
+            {
+                colors.kinds[i] = Some(parse()?).filter(|style| !style.is_empty());
+            }
+        }
+        Ok(colors)
+    }
+
+    /// Returns the style of a key of `LS_COLORS`, like `di`, or of a pattern, like `*.tar`.
+    pub fn get(&self, key: &str) -> Option<Style> {
+        match key.strip_prefix('*') {
+            Some(pattern) => self
+                .patterns
+                .iter()
+                .find(|(p, _)| p == pattern)
+                .map(|(_, style)| *style),
+            None => KEYS
+                .iter()
+                .position(|(k, _)| *k == key)
+                .and_then(|i| self.kinds[i]),
+        }
+    }
+
+    /// Returns the style of a file name, from the metadata of path given by
+    /// `fs::symlink_metadata`. The setuid, setgid, executable and sticky styles are only used
+    /// on Unix. Patterns match the end of the name, the last one defined first, and only if no
+    /// pattern matches with the same case, without case.
+    pub fn style(&self, path: &Path, metadata: &Metadata) -> Option<Style> {
+        let file_type = metadata.file_type();
+        let style = if file_type.is_symlink() {
+            match fs::metadata(path) {
+                Ok(target) if self.link_target => return self.style(path, &target),
+                Ok(_) => self.kind(Kind::Link),
+                Err(_) => self.kind(Kind::Orphan).or(self.kind(Kind::Link)),
+            }
+        } else if file_type.is_dir() {
+            let mode = mode(metadata);
+            let (sticky, writable) = (mode & 0o1000 != 0, mode & 0o002 != 0);
+            self.first(&[
+                (Kind::StickyOtherWritable, sticky && writable),
+                (Kind::OtherWritable, writable),
+                (Kind::Sticky, sticky),
+                (Kind::Dir, true),
+            ])
+        } else if file_type.is_file() {
+            let mode = mode(metadata);
+            self.first(&[
+                (Kind::Setuid, mode & 0o4000 != 0),
+                (Kind::Setgid, mode & 0o2000 != 0),
+                (Kind::Exec, mode & 0o111 != 0),
+                (Kind::MultiHardLink, links(metadata) > 1),
+            ])
+            .or_else(|| self.pattern(path))
+            .or(self.kind(Kind::File))
+        } else {
+            self.special(metadata)
+        };
+        style.or(self.kind(Kind::Normal))
+    }
+
+    /// Returns the style of a file name, reading the metadata of path. Paths which do not
+    /// exist have the `mi` style.
+    pub fn style_for_path(&self, path: &Path) -> Option<Style> {
+        match fs::symlink_metadata(path) {
+            Ok(metadata) => self.style(path, &metadata),
+            Err(_) => self.kind(Kind::Missing).or(self.kind(Kind::Normal)),
+        }
+    }
+
+    fn kind(&self, kind: Kind) -> Option<Style> {
+        self.kinds[kind as usize]
+    }
+
+    /// Returns the style of the first kind which applies and has a style.
+    fn first(&self, kinds: &[(Kind, bool)]) -> Option<Style> {
+        kinds
+            .iter()
+            .filter(|(_, applies)| *applies)
+            .find_map(|(kind, _)| self.kind(*kind))
+    }
+
+    fn pattern(&self, path: &Path) -> Option<Style> {
+        let name = path.file_name()?.to_string_lossy();
+        let name = name.as_bytes();
+        let end = |pattern: &str| name.len().checked_sub(pattern.len()).map(|i| &name[i..]);
+        let patterns = || self.patterns.iter().rev();
+        patterns()
+            .find(|(p, _)| end(p) == Some(p.as_bytes()))
+            .or_else(|| {
+                patterns()
+                    .find(|(p, _)| end(p).is_some_and(|e| e.eq_ignore_ascii_case(p.as_bytes())))
+            })
+            .map(|(_, style)| *style)
+    }
+
+    #[cfg(unix)]
+    fn special(&self, metadata: &Metadata) -> Option<Style> {
+        use std::os::unix::fs::FileTypeExt;
+
+        let file_type = metadata.file_type();
+        self.first(&[
+            (Kind::Fifo, file_type.is_fifo()),
+            (Kind::Socket, file_type.is_socket()),
+            (Kind::BlockDevice, file_type.is_block_device()),
+            (Kind::CharDevice, file_type.is_char_device()),
+        ])
+    }
+
+    #[cfg(not(unix))]
+    fn special(&self, _metadata: &Metadata) -> Option<Style> {
+        None
+    }
+
+    fn set_key(&mut self, key: &str, style: Style) {
+        if let Some(i) = KEYS.iter().position(|(k, _)| *k == key) {
+            self.kinds[i] = Some(style).filter(|style| !style.is_empty());
+        }
+    }
+
+    fn set_pattern(&mut self, pattern: &str, style: Style) {
+        self.patterns.retain(|(p, _)| p != pattern);
+        if !style.is_empty() {
+            self.patterns.push((pattern.to_string(), style));
+        }
+    }
+}
+
+#[cfg(unix)]
+fn mode(metadata: &Metadata) -> u32 {
+    use std::os::unix::fs::PermissionsExt;
+
+    metadata.permissions().mode()
+}
+
+#[cfg(not(unix))]
+fn mode(_metadata: &Metadata) -> u32 {
+    0
+}
+
+#[cfg(unix)]
+fn links(metadata: &Metadata) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+
+    metadata.nlink()
+}
+
+#[cfg(not(unix))]
+fn links(_metadata: &Metadata) -> u64 {
+    1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Color, ParseStyleErrorKind};
+    use std::path::PathBuf;
+
+    /// A directory removed at the end of a test.
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let path = env::temp_dir().join(format!("ansistream-{}-{name}", std::process::id()));
+            let _ = fs::remove_dir_all(&path);
+            fs::create_dir(&path).unwrap();
+            Self(path)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        let colors = LsColors::parse("rs=0:di=01;33:ex=00:*.tar=01;31:*README=4:bogus").unwrap();
+        assert_eq!(Some(Style::new().bold().fg(Color::Brown)), colors.get("di"));
+        // defaults are kept, empty styles unset entries
+        assert_eq!(Some(Style::new().bold().fg(Color::Cyan)), colors.get("ln"));
+        assert_eq!(None, colors.get("ex"));
+        assert_eq!(None, colors.get("rs"));
+        assert_eq!(Some(Style::new().underline()), colors.get("*README"));
+        let error = LsColors::parse("di=01:*.tar=38;5").unwrap_err();
+        assert_eq!(
+            (ParseStyleErrorKind::InvalidSgr, 12),
+            (error.kind(), error.position())
+        );
+    }
+
+    #[test]
+    fn test_dircolors() {
+        let db = "# comment\nTERM xterm*\nCOLOR tty\nDIR 01;33 # dirs\nlink target\n\
+                  EXEC 00\n.tar 01;31\n*README 04\n";
+        let colors = LsColors::from_dircolors(db).unwrap();
+        assert_eq!(Some(Style::new().bold().fg(Color::Brown)), colors.get("di"));
+        assert_eq!(None, colors.get("ex"));
+        assert_eq!(
+            Some(Style::new().bold().fg(Color::Red)),
+            colors.get("*.tar")
+        );
+        assert_eq!(Some(Style::new().underline()), colors.get("*README"));
+        assert!(colors.link_target);
+        let error = LsColors::from_dircolors("DIR 01\nFIFO  40;x\n").unwrap_err();
+        assert_eq!(16, error.position());
+    }
+
+    #[test]
+    fn test_style_for_path() {
+        let dir = TempDir::new("style");
+        let colors = LsColors::parse("fi=37:*.tar=01;31:*.TXT=32:*.txt=33").unwrap();
+        let file = |name: &str| {
+            let path = dir.0.join(name);
+            fs::write(&path, "").unwrap();
+            path
+        };
+        assert_eq!(
+            Some(Style::new().bold().fg(Color::Blue)),
+            colors.style_for_path(&dir.0)
+        );
+        assert_eq!(
+            Some(Style::new().bold().fg(Color::Red)),
+            colors.style_for_path(&file("a.tar"))
+        );
+        // the same case is preferred, then the last pattern
+        assert_eq!(
+            Some(Style::new().fg(Color::Green)),
+            colors.style_for_path(&file("a.TXT"))
+        );
+        assert_eq!(
+            Some(Style::new().fg(Color::Brown)),
+            colors.style_for_path(&file("a.Txt"))
+        );
+        assert_eq!(
+            Some(Style::new().fg(Color::LightGray)),
+            colors.style_for_path(&file("a.rs"))
+        );
+        assert_eq!(None, colors.style_for_path(&dir.0.join("missing")));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_unix_kinds() {
+        use std::os::unix::fs::{symlink, PermissionsExt};
+
+        let dir = TempDir::new("unix");
+        let script = dir.0.join("run.tar");
+        fs::write(&script, "").unwrap();
+        fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
+        // executables have precedence over patterns
+        let colors = LsColors::parse("*.tar=01;31").unwrap();
+        assert_eq!(
+            Some(Style::new().bold().fg(Color::Green)),
+            colors.style_for_path(&script)
+        );
+        let link = dir.0.join("link");
+        symlink(&script, &link).unwrap();
+        assert_eq!(
+            Some(Style::new().bold().fg(Color::Cyan)),
+            colors.style_for_path(&link)
+        );
+        let target = LsColors::parse("ln=target").unwrap();
+        assert_eq!(target.get("ex"), target.style_for_path(&link));
+        let orphan = dir.0.join("orphan");
+        symlink(dir.0.join("missing"), &orphan).unwrap();
+        assert_eq!(
+            Some(Style::new().bold().fg(Color::Red).bg(Color::Black)),
+            colors.style_for_path(&orphan)
+        );
+        let shared = dir.0.join("shared");
+        fs::create_dir(&shared).unwrap();
+        fs::set_permissions(&shared, fs::Permissions::from_mode(0o1777)).unwrap();
+        assert_eq!(
+            Some(Style::new().fg(Color::Black).bg(Color::Green)),
+            colors.style_for_path(&shared)
+        );
+    }
+}
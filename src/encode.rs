@@ -0,0 +1,588 @@
+//! Escape code encoders shared by every writer backend.
+//!
+//! The encoders are written against the small `Sink` trait, which is implemented by adapters
+//! over `io::Write` and `fmt::Write`. Every escape code is plain ascii, so both backends receive
+//! the same output.
+//!
+//! Escape codes are built in an `Escape` buffer on the stack, with the numeric parameters taken
+//! from a precomputed table instead of the core formatting machinery, and written to the sink
+//! with a single call.
+
+use core::fmt;
+#[cfg(feature = "std")]
+use std::io;
+
+use crate::{
+    Attributes, Color, ColorEncoding, Style, BC_DEFAULT, BC_RICH_COLORS, FC_DEFAULT,
+    FC_RICH_COLORS, TS_BLINK, TS_BOLD, TS_DEFAULT, TS_DIM, TS_DOUBLE_UNDERLINE, TS_ENCIRCLED,
+    TS_FONT_1, TS_FONT_9, TS_FONT_DEFAULT, TS_FRAKTUR, TS_FRAMED, TS_HIDDEN, TS_IDEOGRAM_STRESS,
+    TS_IDEOGRAM_UNDERLINE, TS_INVERT, TS_ITALIC, TS_NO_BLINK, TS_NO_BOLD, TS_NO_DIM,
+    TS_NO_FRAMED_ENCIRCLED, TS_NO_HIDDEN, TS_NO_IDEOGRAM, TS_NO_INVERT, TS_NO_ITALIC,
+    TS_NO_ITALIC_FRAKTUR, TS_NO_OVERLINE, TS_NO_PROPORTIONAL_SPACING, TS_NO_STRIKE,
+    TS_NO_SUPERSCRIPT_SUBSCRIPT, TS_NO_UNDERLINE, TS_OVERLINE, TS_PROPORTIONAL_SPACING, TS_STRIKE,
+    TS_SUBSCRIPT, TS_SUPERSCRIPT, TS_UNDERLINE,
+};
+
+/// Output target of the encoders.
+pub(crate) trait Sink {
+    type Error;
+
+    fn put(&mut self, text: &str) -> Result<(), Self::Error>;
+
+    /// Write an escape code, which is always ascii.
+    fn put_escape(&mut self, escape: &Escape) -> Result<(), Self::Error>;
+
+    fn put_fmt(&mut self, fmt: fmt::Arguments<'_>) -> Result<(), Self::Error>;
+}
+
+/// Adapter which writes to an `io::Write`.
+#[cfg(feature = "std")]
+pub(crate) struct IoSink<'a, W: io::Write + ?Sized>(pub &'a mut W);
+
+#[cfg(feature = "std")]
+impl<W: io::Write + ?Sized> Sink for IoSink<'_, W> {
+    type Error = io::Error;
+
+    fn put(&mut self, text: &str) -> io::Result<()> {
+        self.0.write_all(text.as_bytes())
+    }
+
+    fn put_escape(&mut self, escape: &Escape) -> io::Result<()> {
+        self.0.write_all(escape.as_bytes())
+    }
+
+    fn put_fmt(&mut self, fmt: fmt::Arguments<'_>) -> io::Result<()> {
+        self.0.write_fmt(fmt)
+    }
+}
+
+/// Adapter which writes to a `fmt::Write`.
+pub(crate) struct FmtSink<'a, W: fmt::Write + ?Sized>(pub &'a mut W);
+
+impl<W: fmt::Write + ?Sized> Sink for FmtSink<'_, W> {
+    type Error = fmt::Error;
+
+    fn put(&mut self, text: &str) -> fmt::Result {
+        self.0.write_str(text)
+    }
+
+    fn put_escape(&mut self, escape: &Escape) -> fmt::Result {
+        self.0.write_str(escape.as_str())
+    }
+
+    fn put_fmt(&mut self, fmt: fmt::Arguments<'_>) -> fmt::Result {
+        self.0.write_fmt(fmt)
+    }
+}
+
+/// Longest escape code built by the encoders: a style with every attribute and two RGB colors
+/// is about 80 bytes.
+const ESCAPE_CAPACITY: usize = 128;
+
+/// Decimal digits of 0-255, and how many of them are used.
+const DIGITS: [([u8; 3], u8); 256] = {
+    let mut table = [([0; 3], 0); 256];
+    let mut n = 0;
+    while n < 256 {
+        table[n] = if n < 10 {
+            ([b'0' + n as u8, 0, 0], 1)
+        } else if n < 100 {
+            ([b'0' + (n / 10) as u8, b'0' + (n % 10) as u8, 0], 2)
+        } else {
+            (
+                [
+                    b'0' + (n / 100) as u8,
+                    b'0' + (n / 10 % 10) as u8,
+                    b'0' + (n % 10) as u8,
+                ],
+                3,
+            )
+        };
+        n += 1;
+    }
+    table
+};
+
+/// An escape code built on the stack.
+pub(crate) struct Escape {
+    bytes: [u8; ESCAPE_CAPACITY],
+    len: usize,
+}
+
+impl Escape {
+    /// Returns an escape code starting with the control sequence introducer ESC[.
+    fn csi() -> Self {
+        let mut escape = Escape {
+            bytes: [0; ESCAPE_CAPACITY],
+            len: 0,
+        };
+        escape.push(b"\x1b[");
+        escape
+    }
+
+    fn push(&mut self, bytes: &[u8]) {
+        self.bytes[self.len..self.len + bytes.len()].copy_from_slice(bytes);
+        self.len += bytes.len();
+    }
+
+    fn push_num(&mut self, n: u16) {
+        if let Some((digits, len)) = DIGITS.get(usize::from(n)) {
+            self.push(&digits[..usize::from(*len)]);
+            return;
+        }
+        let mut digits = [0; 5];
+        let mut start = digits.len();
+        let mut n = n;
+        while n > 0 {
+            start -= 1;
+            digits[start] = b'0' + (n % 10) as u8;
+            n /= 10;
+        }
+        self.push(&digits[start..]);
+    }
+
+    /// Push a parameter, preceded by a semicolon unless it is the first one.
+    fn push_param(&mut self, n: u16) {
+        if self.len > 2 {
+            self.push(b";");
+        }
+        self.push_num(n);
+    }
+
+    fn as_bytes(&self) -> &[u8] {
+        &self.bytes[..self.len]
+    }
+
+    fn as_str(&self) -> &str {
+        // only ascii is pushed
+        core::str::from_utf8(self.as_bytes()).unwrap_or_default()
+    }
+}
+
+/// Returns the attribute which resets attr. If the attribute is a foreground color, then
+/// it is the default foreground color, and so on.
+pub(crate) fn reset_code(attr: u16) -> u16 {
+    match attr {
+        TS_BOLD => TS_NO_BOLD,
+        TS_DIM => TS_NO_DIM,
+        TS_ITALIC => TS_NO_ITALIC,
+        TS_UNDERLINE => TS_NO_UNDERLINE,
+        TS_BLINK => TS_NO_BLINK,
+        TS_OVERLINE => TS_NO_OVERLINE,
+        TS_INVERT => TS_NO_INVERT,
+        TS_HIDDEN => TS_NO_HIDDEN,
+        TS_STRIKE => TS_NO_STRIKE,
+        TS_FONT_1..=TS_FONT_9 => TS_FONT_DEFAULT,
+        TS_FRAKTUR => TS_NO_ITALIC_FRAKTUR,
+        TS_DOUBLE_UNDERLINE => TS_NO_UNDERLINE,
+        TS_PROPORTIONAL_SPACING => TS_NO_PROPORTIONAL_SPACING,
+        TS_FRAMED | TS_ENCIRCLED => TS_NO_FRAMED_ENCIRCLED,
+        TS_IDEOGRAM_UNDERLINE..=TS_IDEOGRAM_STRESS => TS_NO_IDEOGRAM,
+        TS_SUPERSCRIPT | TS_SUBSCRIPT => TS_NO_SUPERSCRIPT_SUBSCRIPT,
+        FC_RICH_COLORS => FC_DEFAULT,
+        BC_RICH_COLORS => BC_DEFAULT,
+        30..=37 | 90..=97 => FC_DEFAULT,
+        40..=47 | 100..=107 => BC_DEFAULT,
+        _ => TS_DEFAULT,
+    }
+}
+
+pub(crate) fn write_attribute<S: Sink>(s: &mut S, attr: u16) -> Result<(), S::Error> {
+    let mut escape = Escape::csi();
+    escape.push_num(attr);
+    escape.push(b"m");
+    s.put_escape(&escape)
+}
+
+pub(crate) fn reset_attribute<S: Sink>(s: &mut S, attr: u16) -> Result<(), S::Error> {
+    write_attribute(s, reset_code(attr))
+}
+
+pub(crate) fn reset_all_attributes<S: Sink>(s: &mut S) -> Result<(), S::Error> {
+    s.put("\x1b[0m")
+}
+
+pub(crate) fn write_font<S: Sink>(s: &mut S, font: u8) -> Result<(), S::Error> {
+    write_attribute(s, TS_FONT_DEFAULT + u16::from(font.min(9)))
+}
+
+pub(crate) fn write_color_256<S: Sink>(
+    s: &mut S,
+    attr: u16,
+    color: u16,
+    encoding: ColorEncoding,
+) -> Result<(), S::Error> {
+    let mut escape = Escape::csi();
+    push_color_256(&mut escape, attr, color, encoding);
+    escape.push(b"m");
+    s.put_escape(&escape)
+}
+
+pub(crate) fn write_color_rgb<S: Sink>(
+    s: &mut S,
+    attr: u16,
+    rgb: (u16, u16, u16),
+    encoding: ColorEncoding,
+) -> Result<(), S::Error> {
+    let mut escape = Escape::csi();
+    push_color_rgb(&mut escape, attr, rgb, encoding);
+    escape.push(b"m");
+    s.put_escape(&escape)
+}
+
+/// Push the SGR parameters of a 256 color, like 38;5;208.
+fn push_color_256(escape: &mut Escape, attr: u16, color: u16, encoding: ColorEncoding) {
+    escape.push_param(attr);
+    match encoding {
+        ColorEncoding::Semicolon => escape.push(b";5;"),
+        ColorEncoding::Colon | ColorEncoding::ColonWithColorSpace => escape.push(b":5:"),
+    }
+    escape.push_num(color);
+}
+
+/// Push the SGR parameters of a RGB color, like 38;2;255;128;0.
+fn push_color_rgb(
+    escape: &mut Escape,
+    attr: u16,
+    (r, g, b): (u16, u16, u16),
+    encoding: ColorEncoding,
+) {
+    escape.push_param(attr);
+    let separator: &[u8] = match encoding {
+        ColorEncoding::Semicolon => {
+            escape.push(b";2;");
+            b";"
+        }
+        ColorEncoding::Colon => {
+            escape.push(b":2:");
+            b":"
+        }
+        ColorEncoding::ColonWithColorSpace => {
+            escape.push(b":2::");
+            b":"
+        }
+    };
+    escape.push_num(r);
+    escape.push(separator);
+    escape.push_num(g);
+    escape.push(separator);
+    escape.push_num(b);
+}
+
+/// Push the SGR parameters of a color, like 31 or 38;5;208.
+fn push_color(escape: &mut Escape, color: Color, background: bool, encoding: ColorEncoding) {
+    let attr = if background {
+        color.bc_code()
+    } else {
+        color.fc_code()
+    };
+    match color {
+        Color::Ansi256(n) => push_color_256(escape, attr, n.into(), encoding),
+        Color::Rgb(r, g, b) => {
+            push_color_rgb(escape, attr, (r.into(), g.into(), b.into()), encoding)
+        }
+        _ => escape.push_param(attr),
+    }
+}
+
+/// Write every attribute and color of a style as a single SGR sequence. An empty style
+/// writes nothing.
+pub(crate) fn write_style<S: Sink>(
+    s: &mut S,
+    style: &Style,
+    encoding: ColorEncoding,
+) -> Result<(), S::Error> {
+    if style.is_empty() {
+        return Ok(());
+    }
+    let mut escape = Escape::csi();
+    for (attr, code, _) in Attributes::TABLE {
+        if style.attributes.contains(attr) {
+            escape.push_param(code);
+        }
+    }
+    for (i, (attr, _, reset)) in Attributes::TABLE.iter().enumerate() {
+        if style.negated.contains(*attr) && !reset_written(style.negated, i) {
+            escape.push_param(*reset);
+        }
+    }
+    if let Some(color) = style.foreground {
+        push_color(&mut escape, color, false, encoding);
+    }
+    if let Some(color) = style.background {
+        push_color(&mut escape, color, true, encoding);
+    }
+    escape.push(b"m");
+    s.put_escape(&escape)
+}
+
+/// Returns true if an attribute of attributes before index in TABLE has the same reset code as
+/// the attribute at index, like bold and dim.
+fn reset_written(attributes: Attributes, index: usize) -> bool {
+    let reset = Attributes::TABLE[index].2;
+    Attributes::TABLE[..index]
+        .iter()
+        .any(|(other, _, code)| *code == reset && attributes.contains(*other))
+}
+
+/// Reset only the attributes and colors used by a style, as a single SGR sequence.
+pub(crate) fn reset_style<S: Sink>(s: &mut S, style: &Style) -> Result<(), S::Error> {
+    if style.is_empty() {
+        return Ok(());
+    }
+    let mut escape = Escape::csi();
+    for (i, (attr, _, reset)) in Attributes::TABLE.iter().enumerate() {
+        if style.attributes.contains(*attr) && !reset_written(style.attributes, i) {
+            escape.push_param(*reset);
+        }
+    }
+    if style.foreground.is_some() {
+        escape.push_param(FC_DEFAULT);
+    }
+    if style.background.is_some() {
+        escape.push_param(BC_DEFAULT);
+    }
+    escape.push(b"m");
+    s.put_escape(&escape)
+}
+
+/// Write text with a style. If the text is empty, the reset operation will not be performed.
+pub(crate) fn write_text_style<S: Sink>(
+    s: &mut S,
+    style: &Style,
+    encoding: ColorEncoding,
+    text: &str,
+) -> Result<(), S::Error> {
+    write_style(s, style, encoding)?;
+    s.put(text)?;
+    if !text.is_empty() {
+        reset_style(s, style)?;
+    }
+    Ok(())
+}
+
+pub(crate) fn write_text_style_fmt<S: Sink>(
+    s: &mut S,
+    style: &Style,
+    encoding: ColorEncoding,
+    fmt: fmt::Arguments<'_>,
+) -> Result<(), S::Error> {
+    write_style(s, style, encoding)?;
+    s.put_fmt(fmt)?;
+    reset_style(s, style)
+}
+
+/// Write text with a 16 color attribute, converting background colors to foreground colors.
+pub(crate) fn write_text_fc_fmt<S: Sink>(
+    s: &mut S,
+    color: u16,
+    fmt: fmt::Arguments<'_>,
+) -> Result<(), S::Error> {
+    let color = match color {
+        40..=47 | 100..=107 => color - 10,
+        _ => color,
+    };
+    write_attribute(s, color)?;
+    s.put_fmt(fmt)?;
+    reset_attribute(s, color)
+}
+
+/// Write text with a 16 color attribute, converting foreground colors to background colors.
+pub(crate) fn write_text_bc_fmt<S: Sink>(
+    s: &mut S,
+    color: u16,
+    fmt: fmt::Arguments<'_>,
+) -> Result<(), S::Error> {
+    let color = match color {
+        30..=37 | 90..=97 => color + 10,
+        _ => color,
+    };
+    write_attribute(s, color)?;
+    s.put_fmt(fmt)?;
+    reset_attribute(s, color)
+}
+
+/// Write a foreground and a background attribute as a single SGR sequence.
+fn write_color_pair<S: Sink>(s: &mut S, foreground: u16, background: u16) -> Result<(), S::Error> {
+    let mut escape = Escape::csi();
+    escape.push_param(foreground);
+    escape.push_param(background);
+    escape.push(b"m");
+    s.put_escape(&escape)
+}
+
+pub(crate) fn write_text_color<S: Sink>(
+    s: &mut S,
+    foreground: u16,
+    background: u16,
+    text: &str,
+) -> Result<(), S::Error> {
+    write_color_pair(s, foreground, background)?;
+    s.put(text)?;
+    if !text.is_empty() {
+        reset_attribute(s, foreground)?;
+        reset_attribute(s, background)?;
+    }
+    Ok(())
+}
+
+pub(crate) fn write_text_color_fmt<S: Sink>(
+    s: &mut S,
+    foreground: u16,
+    background: u16,
+    fmt: fmt::Arguments<'_>,
+) -> Result<(), S::Error> {
+    write_color_pair(s, foreground, background)?;
+    s.put_fmt(fmt)?;
+    reset_attribute(s, foreground)?;
+    reset_attribute(s, background)
+}
+
+/// Write text with a 256 color. attr is FC_RICH_COLORS or BC_RICH_COLORS. If the text is empty,
+/// the reset operation will not be performed.
+pub(crate) fn write_text_256<S: Sink>(
+    s: &mut S,
+    attr: u16,
+    color: u16,
+    encoding: ColorEncoding,
+    text: &str,
+) -> Result<(), S::Error> {
+    write_color_256(s, attr, color, encoding)?;
+    s.put(text)?;
+    if !text.is_empty() {
+        reset_attribute(s, attr)?;
+    }
+    Ok(())
+}
+
+pub(crate) fn write_text_256_fmt<S: Sink>(
+    s: &mut S,
+    attr: u16,
+    color: u16,
+    encoding: ColorEncoding,
+    fmt: fmt::Arguments<'_>,
+) -> Result<(), S::Error> {
+    write_color_256(s, attr, color, encoding)?;
+    s.put_fmt(fmt)?;
+    reset_attribute(s, attr)
+}
+
+/// Write text with a RGB color. attr is FC_RICH_COLORS or BC_RICH_COLORS. If the text is empty,
+/// the reset operation will not be performed.
+pub(crate) fn write_text_rgb<S: Sink>(
+    s: &mut S,
+    attr: u16,
+    rgb: (u16, u16, u16),
+    encoding: ColorEncoding,
+    text: &str,
+) -> Result<(), S::Error> {
+    write_color_rgb(s, attr, rgb, encoding)?;
+    s.put(text)?;
+    if !text.is_empty() {
+        reset_attribute(s, attr)?;
+    }
+    Ok(())
+}
+
+pub(crate) fn write_text_rgb_fmt<S: Sink>(
+    s: &mut S,
+    attr: u16,
+    rgb: (u16, u16, u16),
+    encoding: ColorEncoding,
+    fmt: fmt::Arguments<'_>,
+) -> Result<(), S::Error> {
+    write_color_rgb(s, attr, rgb, encoding)?;
+    s.put_fmt(fmt)?;
+    reset_attribute(s, attr)
+}
+
+/// Write a control sequence with a single numeric parameter, like ESC[nA.
+pub(crate) fn write_csi<S: Sink>(s: &mut S, n: u16, command: char) -> Result<(), S::Error> {
+    let mut escape = Escape::csi();
+    escape.push_num(n);
+    let mut command_bytes = [0; 4];
+    escape.push(command.encode_utf8(&mut command_bytes).as_bytes());
+    s.put_escape(&escape)
+}
+
+pub(crate) fn cursor_position<S: Sink>(s: &mut S, row: u16, col: u16) -> Result<(), S::Error> {
+    let mut escape = Escape::csi();
+    escape.push_param(row);
+    escape.push_param(col);
+    escape.push(b"H");
+    s.put_escape(&escape)
+}
+
+/// Write the text of an OSC sequence without its C0 control characters and DEL, which would
+/// end the sequence early or be written to the terminal as they are.
+fn put_osc_text<S: Sink>(s: &mut S, text: &str) -> Result<(), S::Error> {
+    text.split(|c: char| c.is_ascii_control())
+        .try_for_each(|part| s.put(part))
+}
+
+pub(crate) fn set_title<S: Sink>(s: &mut S, title: &str) -> Result<(), S::Error> {
+    s.put("\x1b]0;")?;
+    put_osc_text(s, title)?;
+    s.put("\x1b\\")
+}
+
+pub(crate) fn write_hyperlink<S: Sink>(s: &mut S, url: &str, text: &str) -> Result<(), S::Error> {
+    open_hyperlink(s, url)?;
+    s.put(text)?;
+    close_hyperlink(s)
+}
+
+pub(crate) fn open_hyperlink<S: Sink>(s: &mut S, url: &str) -> Result<(), S::Error> {
+    s.put("\x1b]8;;")?;
+    put_osc_text(s, url)?;
+    s.put("\x1b\\")
+}
+
+pub(crate) fn close_hyperlink<S: Sink>(s: &mut S) -> Result<(), S::Error> {
+    s.put("\x1b]8;;\x1b\\")
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_push_num() {
+        for (n, text) in [
+            (0, "0"),
+            (9, "9"),
+            (10, "10"),
+            (99, "99"),
+            (100, "100"),
+            (255, "255"),
+            (256, "256"),
+            (1000, "1000"),
+            (u16::MAX, "65535"),
+        ] {
+            let mut escape = Escape::csi();
+            escape.push_num(n);
+            assert_eq!(text, &escape.as_str()[2..]);
+        }
+    }
+
+    #[test]
+    fn test_escape_capacity() {
+        let mut attributes = Attributes::empty();
+        for (attr, _, _) in Attributes::TABLE {
+            attributes |= attr;
+        }
+        let style = Style::new()
+            .fg(Color::Rgb(255, 255, 255))
+            .bg(Color::Rgb(255, 255, 255))
+            .attr(attributes);
+        let mut text = String::new();
+        write_style(
+            &mut FmtSink(&mut text),
+            &style,
+            ColorEncoding::ColonWithColorSpace,
+        )
+        .unwrap();
+        assert!(text.len() <= ESCAPE_CAPACITY);
+        assert!(text.ends_with(";38:2::255:255:255;48:2::255:255:255m"));
+    }
+}
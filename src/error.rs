@@ -0,0 +1,126 @@
+//! Error type returned by the checked operations of this crate.
+
+use core::{error, fmt};
+
+use crate::MarkupError;
+#[cfg(feature = "std")]
+use std::io;
+
+/// Errors reported by the checked stream operations.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// The underlying writer failed.
+    #[cfg(feature = "std")]
+    Io(io::Error),
+    /// The underlying formatter failed.
+    Fmt(fmt::Error),
+    /// A color component or palette index is out of the 0-255 range.
+    InvalidColor(u16),
+    /// The value is not a known SGR attribute, or the attribute requires additional parameters.
+    InvalidAttribute(u16),
+    /// The style markup is malformed.
+    Markup(MarkupError),
+}
+
+/// Result type used by the checked operations of this crate.
+pub type Result<T> = core::result::Result<T, Error>;
+
+impl Error {
+    /// Returns an error if the color component or index does not fit in 0-255.
+    #[cfg(feature = "std")]
+    pub(crate) fn check_color(color: u16) -> Result<u8> {
+        u8::try_from(color).map_err(|_| Error::InvalidColor(color))
+    }
+
+    /// Returns an error if attr is not a SGR attribute which can be written alone.
+    pub(crate) fn check_attribute(attr: u16) -> Result<u16> {
+        match attr {
+            0..=37 | 39..=47 | 49..=55 | 59..=65 | 73..=75 | 90..=97 | 100..=107 => Ok(attr),
+            _ => Err(Error::InvalidAttribute(attr)),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            #[cfg(feature = "std")]
+            Error::Io(err) => write!(f, "io error: {err}"),
+            Error::Fmt(err) => write!(f, "format error: {err}"),
+            Error::InvalidColor(color) => write!(f, "invalid color value {color}, expected 0-255"),
+            Error::InvalidAttribute(attr) => write!(f, "invalid sgr attribute {attr}"),
+            Error::Markup(err) => write!(f, "invalid markup: {err}"),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            #[cfg(feature = "std")]
+            Error::Io(err) => Some(err),
+            Error::Fmt(err) => Some(err),
+            Error::Markup(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Error::Io(err)
+    }
+}
+
+impl From<fmt::Error> for Error {
+    fn from(err: fmt::Error) -> Self {
+        Error::Fmt(err)
+    }
+}
+
+impl From<MarkupError> for Error {
+    fn from(err: MarkupError) -> Self {
+        Error::Markup(err)
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_color() {
+        assert_eq!(255, Error::check_color(255).unwrap());
+        assert!(matches!(
+            Error::check_color(256),
+            Err(Error::InvalidColor(256))
+        ));
+    }
+
+    #[test]
+    fn test_check_attribute() {
+        assert_eq!(53, Error::check_attribute(53).unwrap());
+        assert!(matches!(
+            Error::check_attribute(38),
+            Err(Error::InvalidAttribute(38))
+        ));
+        assert!(matches!(
+            Error::check_attribute(999),
+            Err(Error::InvalidAttribute(999))
+        ));
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(
+            "invalid color value 999, expected 0-255",
+            Error::InvalidColor(999).to_string()
+        );
+        assert_eq!(
+            "invalid sgr attribute 48",
+            Error::InvalidAttribute(48).to_string()
+        );
+    }
+}
@@ -0,0 +1,341 @@
+//! Extension trait which writes ansi escape codes to any `fmt::Write`.
+
+use core::fmt::{self, Write};
+
+use crate::{
+    encode::{self, FmtSink},
+    markup, ColorEncoding, Error, Result, Style, BC_RICH_COLORS, FC_RICH_COLORS,
+};
+
+const SEMICOLON: ColorEncoding = ColorEncoding::Semicolon;
+
+/// Writes ansi escape codes to any `fmt::Write`, like a `String` or a `fmt::Formatter`.
+/// It offers the same operations as `AnsiWrite`, so styled output can be rendered inside
+/// a `Display` implementation without an `io::Write` adapter.
+///
+/// Like `AnsiWrite`, the trait has no state to hold a `ColorEncoding`: the text, style and
+/// markup methods always write rich colors with `ColorEncoding::Semicolon`, and only
+/// `write_color_256` and `write_color_rgb` take the encoding as a parameter.
+///
+/// # Examples
+///
+/// ```
+/// use std::fmt;
+/// use ansistream::{AnsiFmtWrite, FC_RED};
+///
+/// struct Failure;
+///
+/// impl fmt::Display for Failure {
+///     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+///         f.write_text_fc_fmt(FC_RED, format_args!("failure"))
+///     }
+/// }
+///
+/// assert_eq!("\x1b[31mfailure\x1b[39m", Failure.to_string());
+/// ```
+pub trait AnsiFmtWrite: Write {
+    /// Reset all ansi escape code attributes using ESC[0m.
+    fn reset_all_attributes(&mut self) -> fmt::Result {
+        encode::reset_all_attributes(&mut FmtSink(self))
+    }
+
+    /// Reset an attribute of type(FC, BC, TS). If the attribute is a foreground color, then
+    /// reset it to the default foreground color, and so on.
+    fn reset_attribute(&mut self, attr: u16) -> fmt::Result {
+        encode::reset_attribute(&mut FmtSink(self), attr)
+    }
+
+    /// Write an attribute.
+    fn write_attribute(&mut self, attr: u16) -> fmt::Result {
+        encode::write_attribute(&mut FmtSink(self), attr)
+    }
+
+    /// Write an attribute, rejecting values that are not known SGR attributes
+    /// or that require additional parameters, like FC_RICH_COLORS.
+    fn try_write_attribute(&mut self, attr: u16) -> Result<()> {
+        self.write_attribute(Error::check_attribute(attr)?)?;
+        Ok(())
+    }
+
+    /// Select one of the alternative fonts (SGR 11-19). Font 0 restores the primary font,
+    /// any value above 9 is clamped to the last alternative font.
+    fn write_font(&mut self, font: u8) -> fmt::Result {
+        encode::write_font(&mut FmtSink(self), font)
+    }
+
+    /// Write a 256 color attribute. attr is FC_RICH_COLORS or BC_RICH_COLORS.
+    fn write_color_256(&mut self, attr: u16, color: u16, encoding: ColorEncoding) -> fmt::Result {
+        encode::write_color_256(&mut FmtSink(self), attr, color, encoding)
+    }
+
+    /// Write a RGB color attribute. attr is FC_RICH_COLORS or BC_RICH_COLORS.
+    fn write_color_rgb(
+        &mut self,
+        attr: u16,
+        r: u16,
+        g: u16,
+        b: u16,
+        encoding: ColorEncoding,
+    ) -> fmt::Result {
+        encode::write_color_rgb(&mut FmtSink(self), attr, (r, g, b), encoding)
+    }
+
+    /// Write a 16 formatted foreground color text. The attribute is reseted at the end of operation.
+    fn write_text_fc_fmt(&mut self, color: u16, fmt: fmt::Arguments<'_>) -> fmt::Result {
+        encode::write_text_fc_fmt(&mut FmtSink(self), color, fmt)
+    }
+
+    /// Write a 16 formatted background color text. The attribute is reseted at the end of operation.
+    fn write_text_bc_fmt(&mut self, color: u16, fmt: fmt::Arguments<'_>) -> fmt::Result {
+        encode::write_text_bc_fmt(&mut FmtSink(self), color, fmt)
+    }
+
+    /// Write 16 foregournd and background color text. If the text is
+    /// empty, the reset operation will not be performed. Only the codes from foreground
+    /// and backround color are reseted.
+    fn write_text_color(&mut self, foreground: u16, background: u16, text: &str) -> fmt::Result {
+        encode::write_text_color(&mut FmtSink(self), foreground, background, text)
+    }
+
+    /// Write 16 foreground and background color formatted text.
+    /// Only the codes from foreground and background are reset.
+    fn write_text_color_fmt(
+        &mut self,
+        foreground: u16,
+        background: u16,
+        fmt: fmt::Arguments<'_>,
+    ) -> fmt::Result {
+        encode::write_text_color_fmt(&mut FmtSink(self), foreground, background, fmt)
+    }
+
+    /// Write 256 foreground color text. If the text is empty, the
+    /// reset operation will not be performed.
+    fn write_text_fc256(&mut self, color: u16, text: &str) -> fmt::Result {
+        encode::write_text_256(&mut FmtSink(self), FC_RICH_COLORS, color, SEMICOLON, text)
+    }
+
+    /// Write 256 foreground color formatted text. A partial reset operation of used codes
+    /// will be performed.
+    fn write_text_fc256_fmt(&mut self, color: u16, fmt: fmt::Arguments<'_>) -> fmt::Result {
+        encode::write_text_256_fmt(&mut FmtSink(self), FC_RICH_COLORS, color, SEMICOLON, fmt)
+    }
+
+    /// Write 256 background color text. If the text is empty, the
+    /// reset operation will not be performed.
+    fn write_text_bc256(&mut self, color: u16, text: &str) -> fmt::Result {
+        encode::write_text_256(&mut FmtSink(self), BC_RICH_COLORS, color, SEMICOLON, text)
+    }
+
+    /// Write 256 background color formatted text. A partial reset operation of used codes
+    /// will be performed.
+    fn write_text_bc256_fmt(&mut self, color: u16, fmt: fmt::Arguments<'_>) -> fmt::Result {
+        encode::write_text_256_fmt(&mut FmtSink(self), BC_RICH_COLORS, color, SEMICOLON, fmt)
+    }
+
+    /// Write RGB foreground color text. If the text is empty, the
+    /// reset operation will not be performed.
+    fn write_text_fcrgb(&mut self, r: u16, g: u16, b: u16, text: &str) -> fmt::Result {
+        encode::write_text_rgb(
+            &mut FmtSink(self),
+            FC_RICH_COLORS,
+            (r, g, b),
+            SEMICOLON,
+            text,
+        )
+    }
+
+    /// Write RGB formatted foreground color text. A partial reset operation
+    /// is performed.
+    fn write_text_fcrgb_fmt(
+        &mut self,
+        r: u16,
+        g: u16,
+        b: u16,
+        fmt: fmt::Arguments<'_>,
+    ) -> fmt::Result {
+        encode::write_text_rgb_fmt(
+            &mut FmtSink(self),
+            FC_RICH_COLORS,
+            (r, g, b),
+            SEMICOLON,
+            fmt,
+        )
+    }
+
+    /// Write RGB background color text. If the text is empty, the
+    /// reset operation will not be performed.
+    fn write_text_bcrgb(&mut self, r: u16, g: u16, b: u16, text: &str) -> fmt::Result {
+        encode::write_text_rgb(
+            &mut FmtSink(self),
+            BC_RICH_COLORS,
+            (r, g, b),
+            SEMICOLON,
+            text,
+        )
+    }
+
+    /// Write formatted RGB background color text. A partial reset operation is performed.
+    fn write_text_bcrgb_fmt(
+        &mut self,
+        r: u16,
+        g: u16,
+        b: u16,
+        fmt: fmt::Arguments<'_>,
+    ) -> fmt::Result {
+        encode::write_text_rgb_fmt(
+            &mut FmtSink(self),
+            BC_RICH_COLORS,
+            (r, g, b),
+            SEMICOLON,
+            fmt,
+        )
+    }
+
+    /// Write the attributes and colors of a style as a single SGR sequence.
+    fn write_style(&mut self, style: &Style) -> fmt::Result {
+        encode::write_style(&mut FmtSink(self), style, SEMICOLON)
+    }
+
+    /// Reset only the attributes and colors used by a style.
+    fn reset_style(&mut self, style: &Style) -> fmt::Result {
+        encode::reset_style(&mut FmtSink(self), style)
+    }
+
+    /// Write styled text. If the text is empty, the reset operation will not be performed.
+    fn write_text_style(&mut self, style: &Style, text: &str) -> fmt::Result {
+        encode::write_text_style(&mut FmtSink(self), style, SEMICOLON, text)
+    }
+
+    /// Write styled formatted text. The style is reseted at the end of operation.
+    fn write_text_style_fmt(&mut self, style: &Style, fmt: fmt::Arguments<'_>) -> fmt::Result {
+        encode::write_text_style_fmt(&mut FmtSink(self), style, SEMICOLON, fmt)
+    }
+
+    /// Move the cursor n cells up using ESC[nA.
+    fn cursor_up(&mut self, n: u16) -> fmt::Result {
+        encode::write_csi(&mut FmtSink(self), n, 'A')
+    }
+
+    /// Move the cursor n cells down using ESC[nB.
+    fn cursor_down(&mut self, n: u16) -> fmt::Result {
+        encode::write_csi(&mut FmtSink(self), n, 'B')
+    }
+
+    /// Move the cursor n cells forward using ESC[nC.
+    fn cursor_forward(&mut self, n: u16) -> fmt::Result {
+        encode::write_csi(&mut FmtSink(self), n, 'C')
+    }
+
+    /// Move the cursor n cells back using ESC[nD.
+    fn cursor_back(&mut self, n: u16) -> fmt::Result {
+        encode::write_csi(&mut FmtSink(self), n, 'D')
+    }
+
+    /// Move the cursor to row and column using ESC[row;colH. Both values are 1-based.
+    fn cursor_position(&mut self, row: u16, col: u16) -> fmt::Result {
+        encode::cursor_position(&mut FmtSink(self), row, col)
+    }
+
+    /// Save the cursor position using ESC7.
+    fn save_cursor_position(&mut self) -> fmt::Result {
+        self.write_str("\x1b7")
+    }
+
+    /// Restore the cursor position saved by `save_cursor_position` using ESC8.
+    fn restore_cursor_position(&mut self) -> fmt::Result {
+        self.write_str("\x1b8")
+    }
+
+    /// Hide the cursor using ESC[?25l.
+    fn hide_cursor(&mut self) -> fmt::Result {
+        self.write_str("\x1b[?25l")
+    }
+
+    /// Show the cursor using ESC[?25h.
+    fn show_cursor(&mut self) -> fmt::Result {
+        self.write_str("\x1b[?25h")
+    }
+
+    /// Clear the entire screen using ESC[2J. The cursor position is not changed.
+    fn clear_screen(&mut self) -> fmt::Result {
+        encode::write_csi(&mut FmtSink(self), 2, 'J')
+    }
+
+    /// Clear the entire line using ESC[2K. The cursor position is not changed.
+    fn clear_line(&mut self) -> fmt::Result {
+        encode::write_csi(&mut FmtSink(self), 2, 'K')
+    }
+
+    /// Set the terminal window title using the OSC 0 sequence. Control characters of the title
+    /// are not written.
+    fn set_title(&mut self, title: &str) -> fmt::Result {
+        encode::set_title(&mut FmtSink(self), title)
+    }
+
+    /// Write text as a hyperlink to url using the OSC 8 sequence. Control characters of the url
+    /// are not written.
+    fn write_hyperlink(&mut self, url: &str, text: &str) -> fmt::Result {
+        encode::write_hyperlink(&mut FmtSink(self), url, text)
+    }
+
+    /// Write style markup, like `[bold red]error:[/] text`. The markup is validated before
+    /// anything is written, see `Markup` for its syntax.
+    fn write_markup(&mut self, markup: &str) -> Result<()> {
+        markup::write_markup(
+            &mut FmtSink(self),
+            markup,
+            SEMICOLON,
+            true,
+            &mut markup::keep,
+        )?;
+        Ok(())
+    }
+}
+
+impl<W: Write + ?Sized> AnsiFmtWrite for W {}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::{BC_DARK_GRAY, FC_GREEN, FC_MAGENTA, TS_BOLD};
+
+    #[test]
+    fn test_write_attributes() {
+        let mut text = String::new();
+        text.write_attribute(TS_BOLD).unwrap();
+        text.reset_attribute(TS_BOLD).unwrap();
+        text.reset_all_attributes().unwrap();
+        assert!(matches!(
+            text.try_write_attribute(FC_RICH_COLORS),
+            Err(Error::InvalidAttribute(38))
+        ));
+        assert_eq!("\x1b[1m\x1b[22m\x1b[0m", text);
+    }
+
+    #[test]
+    fn test_write_text_colors() {
+        let mut text = String::new();
+        text.write_text_color(FC_MAGENTA, BC_DARK_GRAY, "a")
+            .unwrap();
+        text.write_text_fc256_fmt(FC_GREEN, format_args!("{}", 'b'))
+            .unwrap();
+        text.write_color_rgb(BC_RICH_COLORS, 1, 2, 3, ColorEncoding::Colon)
+            .unwrap();
+        assert_eq!(
+            "\x1b[35;100ma\x1b[39m\x1b[49m\x1b[38;5;32mb\x1b[39m\x1b[48:2:1:2:3m",
+            text
+        );
+    }
+
+    #[test]
+    fn test_cursor_and_osc_operations() {
+        let mut text = String::new();
+        text.cursor_position(1, 1).unwrap();
+        text.clear_screen().unwrap();
+        text.write_hyperlink("https://x", "x").unwrap();
+        assert_eq!(
+            "\x1b[1;1H\x1b[2J\x1b]8;;https://x\x1b\\x\x1b]8;;\x1b\\",
+            text
+        );
+    }
+}
@@ -0,0 +1,285 @@
+//! Text colored with gradients across color stops.
+
+use crate::{
+    adjust::ColorAdjust,
+    colorspace::{Hsl, Oklab},
+    encode::{self, Sink},
+    ColorEncoding, Rgb, Style,
+};
+
+/// The color space in which a gradient blends its stops.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Interpolation {
+    /// Blend the sRGB components. Blends of complementary colors pass through gray.
+    Rgb,
+    /// Blend hue, saturation and lightness, taking the shortest way around the hue circle.
+    Hsl,
+    /// Blend in OKLab, which keeps the perceived lightness even.
+    #[default]
+    Oklab,
+}
+
+/// Colors evenly spread across two or more stops.
+///
+/// # Examples
+///
+/// ```
+/// use ansistream::{Gradient, Interpolation};
+///
+/// let gradient = Gradient::new([(255, 0, 0), (0, 0, 255)]).interpolation(Interpolation::Rgb);
+/// assert_eq!((255, 0, 0), gradient.at(0.0));
+/// assert_eq!((128, 0, 128), gradient.at(0.5));
+/// assert_eq!((0, 0, 255), gradient.at(1.0));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Gradient {
+    stops: Vec<Rgb>,
+    interpolation: Interpolation,
+}
+
+impl Gradient {
+    /// Returns a gradient over stops, blended in OKLab.
+    ///
+    /// # Panics
+    ///
+    /// Panics if stops is empty.
+    pub fn new(stops: impl IntoIterator<Item = Rgb>) -> Self {
+        let stops: Vec<Rgb> = stops.into_iter().collect();
+        assert!(
+            !stops.is_empty(),
+            "a gradient needs at least one color stop"
+        );
+        Gradient {
+            stops,
+            interpolation: Interpolation::default(),
+        }
+    }
+
+    /// Set the color space in which the stops are blended.
+    pub fn interpolation(mut self, interpolation: Interpolation) -> Self {
+        self.interpolation = interpolation;
+        self
+    }
+
+    /// Returns the color stops.
+    pub fn stops(&self) -> &[Rgb] {
+        &self.stops
+    }
+
+    /// Returns the color at position t, from 0 at the first stop to 1 at the last one. Values out
+    /// of range are clamped.
+    pub fn at(&self, t: f32) -> Rgb {
+        let segments = self.stops.len() - 1;
+        if segments == 0 {
+            return self.stops[0];
+        }
+        let t = t.clamp(0.0, 1.0) * segments as f32;
+        let index = (t as usize).min(segments - 1);
+        let (from, to) = (self.stops[index], self.stops[index + 1]);
+        let t = t - index as f32;
+        match self.interpolation {
+            Interpolation::Rgb => {
+                let mix = |a: u8, b: u8| lerp(a.into(), b.into(), t).round() as u8;
+                (mix(from.0, to.0), mix(from.1, to.1), mix(from.2, to.2))
+            }
+            Interpolation::Hsl => {
+                let (a, b) = (Hsl::from_rgb(from), Hsl::from_rgb(to));
+                // an achromatic stop takes the hue of the other one
+                let (ha, hb) = match (a.s == 0.0, b.s == 0.0) {
+                    (true, false) => (b.h, b.h),
+                    (false, true) => (a.h, a.h),
+                    _ => (a.h, b.h),
+                };
+                let delta = (hb - ha + 540.0).rem_euclid(360.0) - 180.0;
+                Hsl {
+                    h: (ha + delta * t).rem_euclid(360.0),
+                    s: lerp(a.s, b.s, t),
+                    l: lerp(a.l, b.l, t),
+                }
+                .to_rgb()
+            }
+            Interpolation::Oklab => {
+                let (a, b) = (Oklab::from_rgb(from), Oklab::from_rgb(to));
+                Oklab {
+                    l: lerp(a.l, b.l, t),
+                    a: lerp(a.a, b.a, t),
+                    b: lerp(a.b, b.b, t),
+                }
+                .to_rgb()
+            }
+        }
+    }
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+/// Returns true if c is drawn together with the character before it: combining marks,
+/// variation selectors, zero width joiners and the character following a joiner.
+pub(crate) fn extends(c: char) -> bool {
+    matches!(c,
+        '\u{0300}'..='\u{036f}'
+        | '\u{1ab0}'..='\u{1aff}'
+        | '\u{1dc0}'..='\u{1dff}'
+        | '\u{200d}'
+        | '\u{20d0}'..='\u{20ff}'
+        | '\u{fe00}'..='\u{fe0f}'
+        | '\u{fe20}'..='\u{fe2f}'
+        | '\u{1f3fb}'..='\u{1f3ff}'
+        | '\u{e0100}'..='\u{e01ef}')
+}
+
+/// Splits text in approximate graphemes: characters with their combining marks, and emoji
+/// joined by zero width joiners.
+fn clusters(text: &str) -> impl Iterator<Item = &str> {
+    let mut rest = text;
+    core::iter::from_fn(move || {
+        let mut chars = rest.char_indices();
+        let (_, first) = chars.next()?;
+        let mut joined = first == '\u{200d}';
+        let mut end = rest.len();
+        for (i, c) in chars {
+            if !(joined || extends(c)) {
+                end = i;
+                break;
+            }
+            joined = c == '\u{200d}';
+        }
+        let (cluster, tail) = rest.split_at(end);
+        rest = tail;
+        Some(cluster)
+    })
+}
+
+/// Write text with a color of the gradients for each grapheme. Escape codes are only written
+/// when the color changes, and the used colors are reset at the end.
+pub(crate) fn write_gradient<S: Sink>(
+    s: &mut S,
+    text: &str,
+    fg: Option<&Gradient>,
+    bg: Option<&Gradient>,
+    encoding: ColorEncoding,
+    colors: &mut ColorAdjust,
+) -> Result<(), S::Error> {
+    let count = clusters(text).count();
+    if count == 0 || (fg.is_none() && bg.is_none()) {
+        return s.put(text);
+    }
+    let step = 1.0 / (count.max(2) - 1) as f32;
+    let mut last = Style::new();
+    for (i, cluster) in clusters(text).enumerate() {
+        let t = i as f32 * step;
+        let style = Style {
+            foreground: fg.map(|g| colors.downgrade(g.at(t))),
+            background: bg.map(|g| colors.downgrade(g.at(t))),
+            ..Style::new()
+        };
+        let style = colors.style(&style);
+        if style != last {
+            encode::write_style(s, &style, encoding)?;
+            last = style;
+        }
+        s.put(cluster)?;
+    }
+    encode::reset_style(s, &last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{encode::IoSink, ColorDepth};
+
+    fn render(
+        text: &str,
+        fg: Option<&Gradient>,
+        bg: Option<&Gradient>,
+        depth: ColorDepth,
+    ) -> String {
+        let mut buffer = Vec::new();
+        let mut colors = ColorAdjust {
+            depth,
+            ..ColorAdjust::default()
+        };
+        write_gradient(
+            &mut IoSink(&mut buffer),
+            text,
+            fg,
+            bg,
+            ColorEncoding::Semicolon,
+            &mut colors,
+        )
+        .unwrap();
+        String::from_utf8(buffer).unwrap()
+    }
+
+    #[test]
+    fn test_interpolation() {
+        let stops = [(255, 0, 0), (0, 255, 0), (0, 0, 255)];
+        for interpolation in [Interpolation::Rgb, Interpolation::Hsl, Interpolation::Oklab] {
+            let gradient = Gradient::new(stops).interpolation(interpolation);
+            assert_eq!((255, 0, 0), gradient.at(-1.0));
+            assert_eq!((0, 255, 0), gradient.at(0.5));
+            assert_eq!((0, 0, 255), gradient.at(2.0));
+        }
+        let gradient = Gradient::new([(255, 0, 0), (0, 255, 0)]);
+        assert_eq!(
+            (128, 128, 0),
+            gradient.interpolation(Interpolation::Rgb).at(0.5)
+        );
+        let gradient = Gradient::new([(255, 0, 0), (0, 255, 0)]);
+        assert_eq!(
+            (255, 255, 0),
+            gradient.interpolation(Interpolation::Hsl).at(0.5)
+        );
+        // the hue takes the short way, from red to magenta
+        let gradient = Gradient::new([(255, 0, 0), (0, 0, 255)]).interpolation(Interpolation::Hsl);
+        assert_eq!((255, 0, 255), gradient.at(0.5));
+        assert_eq!((7, 7, 7), Gradient::new([(7, 7, 7)]).at(0.3));
+    }
+
+    #[test]
+    fn test_clusters() {
+        let text = "ae\u{301}\u{1f469}\u{200d}\u{1f4bb}!";
+        let clusters: Vec<&str> = clusters(text).collect();
+        assert_eq!(
+            vec!["a", "e\u{301}", "\u{1f469}\u{200d}\u{1f4bb}", "!"],
+            clusters
+        );
+        assert_eq!(0, super::clusters("").count());
+    }
+
+    #[test]
+    fn test_write_gradient() {
+        let gradient = Gradient::new([(255, 0, 0), (0, 0, 255)]).interpolation(Interpolation::Rgb);
+        assert_eq!(
+            "\x1b[38;2;255;0;0ma\x1b[38;2;128;0;128mb\x1b[38;2;0;0;255mc\x1b[39m",
+            render("abc", Some(&gradient), None, ColorDepth::TrueColor)
+        );
+        assert_eq!(
+            "\x1b[38;2;255;0;0;48;2;0;0;255ma\x1b[39;49m",
+            render(
+                "a",
+                Some(&gradient),
+                Some(&Gradient::new([(0, 0, 255), (255, 0, 0)])),
+                ColorDepth::TrueColor
+            )
+        );
+        assert_eq!(
+            "\x1b[48;5;196ma\x1b[48;5;90mb\x1b[48;5;21mc\x1b[49m",
+            render("abc", None, Some(&gradient), ColorDepth::Ansi256)
+        );
+        // repeated colors are written once
+        let red = Gradient::new([(255, 0, 0)]);
+        assert_eq!(
+            "\x1b[48;5;196mabc\x1b[49m",
+            render("abc", None, Some(&red), ColorDepth::Ansi256)
+        );
+        assert_eq!(
+            "\x1b[91ma\x1b[35mb\x1b[34mc\x1b[39m",
+            render("abc", Some(&gradient), None, ColorDepth::Ansi16)
+        );
+        assert_eq!("abc", render("abc", None, None, ColorDepth::TrueColor));
+        assert_eq!("", render("", Some(&gradient), None, ColorDepth::TrueColor));
+    }
+}
@@ -19,12 +19,20 @@
 //! |2 | faint or decreased insensity|
 //! |4 | singly underlined|
 //! |5 | slow blink|
+//! |10-19 | primary and alternative fonts|
+//! |20 | fraktur (gothic)|
+//! |21 | doubly underlined|
+//! |26 | proportional spacing|
 //! |30-37 | foreground color (3/4 bit)|
 //! |38;5;x | foreground color (256 colors, non-standard)|
 //! |38;2;r;g;b | foreground color (RGB, non-standard)|
 //! |40-47 | background color (8 colors)|
 //! |48;5;x | background color (256 colors, non-standard)|
 //! |48;2;r;g;b | background color (RGB, non-standard)|
+//! |51-52 | framed, encircled|
+//! |53 | overlined|
+//! |60-65 | ideogram underline, overline and stress marking|
+//! |73-74 | superscript, subscript (mintty, VTE)|
 //! |90-97 | bright foreground color (non-standard)|
 //! |100-107 | bright background color (non-standard)|
 //!
@@ -33,19 +41,22 @@
 //! * Write FC_RED attribute to stream
 //!
 //! ```
+//! # #[cfg(feature = "std")] {
 //! use ansistream::{FC_RED, AnsiEscapeStream};
 //! use std::io::Cursor;
-//! 
+//!
 //! let buffer = Cursor::new(Vec::<u8>::new());
 //! let mut astream = AnsiEscapeStream::new(buffer);
 //! astream.write_attribute(FC_RED).unwrap();
 //! // fcred escape code
 //! assert_eq!(&[0x1b, 0x5b, 0x33, 0x31, 0x6d], astream.buffer());
+//! # }
 //! ```
 //!
 //! * Reset an attribute in stream
 //!
 //! ```
+//! # #[cfg(feature = "std")] {
 //! use ansistream::{FC_RED, AnsiEscapeStream};
 //! use std::io::Cursor;
 //!
@@ -54,11 +65,13 @@
 //! astream.reset_attribute(FC_RED).unwrap();
 //! // fcred escape code
 //! assert_eq!(&[0x1b, 0x5b, 0x33, 0x39, 0x6d], astream.buffer());
+//! # }
 //! ```
 //!
 //! * Write formatted foreground green color text to stream.
 //!
 //! ```
+//! # #[cfg(feature = "std")] {
 //! use ansistream::{FC_GREEN, AnsiEscapeStream};
 //! use std::io::Cursor;
 //!
@@ -72,17 +85,118 @@
 //!     &[0x1b, 0x5b, 0x33, 0x32, 0x6d, 0x31, 0x32, 0x33, 0x1b, 0x5b, 0x33, 0x39, 0x6d],
 //!     astream.buffer()
 //! );
+//! # }
+//! ```
+//!
+//! ## Features
+//!
+//! * **std** (default): enables `AnsiEscapeStream` and the `AnsiWrite` trait over `std::io::Write`.
+//!   Without it the crate is `no_std` and writes escape codes to any `core::fmt::Write`, like a
+//!   serial console, through the `AnsiFmtWrite` trait and `Styled` values.
+//!
+//! ```
+//! use ansistream::{AnsiFmtWrite, FC_RED};
+//! use core::fmt;
+//!
+//! struct Uart;
+//!
+//! impl fmt::Write for Uart {
+//!     fn write_str(&mut self, s: &str) -> fmt::Result {
+//!         // push s.as_bytes() to the transmit register
+//!         Ok(())
+//!     }
+//! }
+//!
+//! let mut uart = Uart;
+//! uart.write_text_fc_fmt(FC_RED, format_args!("overcurrent on channel {}", 3))
+//!     .unwrap();
 //! ```
 
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(feature = "std")]
+mod adjust;
+#[cfg(feature = "std")]
+mod buffer;
+mod color;
+mod color_names;
+#[cfg(feature = "std")]
+mod colorspace;
+#[cfg(feature = "std")]
+mod contrast;
+#[cfg(feature = "std")]
+mod cvd;
+#[cfg(feature = "std")]
+mod dircolors;
+mod encode;
+mod error;
+mod fmt_write;
+#[cfg(feature = "std")]
+mod gradient;
+mod markup;
+mod palette;
+#[cfg(feature = "std")]
+mod rainbow;
+#[cfg(feature = "std")]
+mod scheme;
+mod sgr;
+mod stack;
+mod style;
+#[cfg(feature = "std")]
+mod style_map;
+mod styled;
+mod theme;
+#[cfg(feature = "std")]
+mod write;
+
+#[cfg(feature = "std")]
+pub use buffer::{Checkpoint, FlushPolicy, StreamBuffer};
+pub use color::{Color, ParseColorError, ParseColorErrorKind};
+#[cfg(feature = "std")]
+pub use colorspace::{
+    nearest_ansi16, nearest_ansi256, DistanceMetric, Hsl, Hsv, Lab, NearestCache, Oklab,
+};
+#[cfg(feature = "std")]
+pub use contrast::{
+    contrast_ratio, ensure_contrast, relative_luminance, WCAG_AA, WCAG_AAA, WCAG_AA_LARGE,
+};
+#[cfg(feature = "std")]
+pub use cvd::Deficiency;
+#[cfg(feature = "std")]
+pub use dircolors::LsColors;
+pub use error::{Error, Result};
+pub use fmt_write::AnsiFmtWrite;
+#[cfg(feature = "std")]
+pub use gradient::{Gradient, Interpolation};
+pub use markup::{Markup, MarkupError, MarkupErrorKind, MAX_MARKUP_DEPTH};
+pub use palette::{xterm_cube, xterm_gray, xterm_index, Rgb, CUBE_LEVELS, XTERM_PALETTE};
+#[cfg(feature = "std")]
+pub use rainbow::Rainbow;
+#[cfg(feature = "std")]
+pub use scheme::{ColorScheme, ParseSchemeError, ParseSchemeErrorKind};
+pub use stack::{Overflow, StackBuffer};
+pub use style::{Attributes, ParseStyleError, ParseStyleErrorKind, Style};
+#[cfg(feature = "std")]
+pub use style_map::{StyleMap, GCC_DEFAULTS, GREP_DEFAULTS};
+#[cfg(feature = "std")]
+pub use styled::set_thread_colors_enabled;
+pub use styled::{colors_enabled, set_colors_enabled, Styled, Stylize};
+pub use theme::{Role, Theme};
+#[cfg(feature = "std")]
+pub use write::AnsiWrite;
+
+#[cfg(feature = "std")]
+use adjust::ColorAdjust;
+#[cfg(feature = "std")]
+use encode::IoSink;
+#[cfg(feature = "std")]
 use std::{
     fmt::{self, Arguments},
-    io::{self, BufWriter, Write},
+    io::{self, Write},
     ops::{Deref, DerefMut},
+    path::Path,
 };
 
-/// ASCII Escape.
-const ESC: u8 = 0x1b;
-
 /// Text Styles
 pub const TS_RESET_ALL: u16 = 0;
 pub const TS_BOLD: u16 = 1;
@@ -90,20 +204,55 @@ pub const TS_DIM: u16 = 2;
 pub const TS_ITALIC: u16 = 3;
 pub const TS_UNDERLINE: u16 = 4;
 pub const TS_BLINK: u16 = 5;
-pub const TS_OVERLINE: u16 = 6;
+pub const TS_OVERLINE: u16 = 53;
 pub const TS_INVERT: u16 = 7;
 pub const TS_HIDDEN: u16 = 8;
 pub const TS_STRIKE: u16 = 9;
-pub const TS_DEFAULT: u16 = 20;
-pub const TS_NO_BOLD: u16 = 21;
+pub const TS_DEFAULT: u16 = 0;
+pub const TS_NO_BOLD: u16 = 22;
 pub const TS_NO_DIM: u16 = 22;
-pub const TS_NO_ITALIC: u16 = 0; // 23; not working
+pub const TS_NO_ITALIC: u16 = 23;
 pub const TS_NO_UNDERLINE: u16 = 24;
 pub const TS_NO_BLINK: u16 = 25;
-pub const TS_NO_OVERLINE: u16 = 26;
+pub const TS_NO_OVERLINE: u16 = 55;
 pub const TS_NO_INVERT: u16 = 27;
 pub const TS_NO_HIDDEN: u16 = 28;
 pub const TS_NO_STRIKE: u16 = 29;
+/// Alternative fonts.
+pub const TS_FONT_DEFAULT: u16 = 10;
+pub const TS_FONT_1: u16 = 11;
+pub const TS_FONT_2: u16 = 12;
+pub const TS_FONT_3: u16 = 13;
+pub const TS_FONT_4: u16 = 14;
+pub const TS_FONT_5: u16 = 15;
+pub const TS_FONT_6: u16 = 16;
+pub const TS_FONT_7: u16 = 17;
+pub const TS_FONT_8: u16 = 18;
+pub const TS_FONT_9: u16 = 19;
+/// Rarely supported text styles.
+pub const TS_FRAKTUR: u16 = 20;
+pub const TS_DOUBLE_UNDERLINE: u16 = 21;
+pub const TS_NO_ITALIC_FRAKTUR: u16 = 23;
+pub const TS_PROPORTIONAL_SPACING: u16 = 26;
+pub const TS_NO_PROPORTIONAL_SPACING: u16 = 50;
+pub const TS_FRAMED: u16 = 51;
+pub const TS_ENCIRCLED: u16 = 52;
+#[deprecated(note = "use TS_OVERLINE, which has the same value")]
+pub const TS_OVERLINED: u16 = TS_OVERLINE;
+pub const TS_NO_FRAMED_ENCIRCLED: u16 = 54;
+#[deprecated(note = "use TS_NO_OVERLINE, which has the same value")]
+pub const TS_NO_OVERLINED: u16 = TS_NO_OVERLINE;
+/// Ideogram attributes.
+pub const TS_IDEOGRAM_UNDERLINE: u16 = 60;
+pub const TS_IDEOGRAM_DOUBLE_UNDERLINE: u16 = 61;
+pub const TS_IDEOGRAM_OVERLINE: u16 = 62;
+pub const TS_IDEOGRAM_DOUBLE_OVERLINE: u16 = 63;
+pub const TS_IDEOGRAM_STRESS: u16 = 64;
+pub const TS_NO_IDEOGRAM: u16 = 65;
+/// Superscript and subscript (mintty, VTE).
+pub const TS_SUPERSCRIPT: u16 = 73;
+pub const TS_SUBSCRIPT: u16 = 74;
+pub const TS_NO_SUPERSCRIPT_SUBSCRIPT: u16 = 75;
 /// Foreground colors.
 pub const FC_BLACK: u16 = 30;
 pub const FC_RED: u16 = 31;
@@ -144,63 +293,246 @@ pub const BC_LIGHT_MAGENTA: u16 = 105;
 pub const BC_LIGHT_CYAN: u16 = 106;
 pub const BC_WHITE: u16 = 107;
 
+/// Parameter encoding used for rich (256 and RGB) colors.
+///
+/// |Encoding | 256 colors | RGB|
+/// |- | - | -|
+/// |Semicolon | 38;5;x | 38;2;r;g;b|
+/// |Colon | 38:5:x | 38:2:r:g:b|
+/// |ColonWithColorSpace | 38:5:x | 38:2::r:g:b|
+///
+/// The colon forms are the ITU T.416 subparameter syntax. `ColonWithColorSpace` leaves the
+/// color space id empty, which is the form most standards conforming terminals expect.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorEncoding {
+    #[default]
+    Semicolon,
+    Colon,
+    ColonWithColorSpace,
+}
+
+/// Colors a terminal can show. Gradients are downgraded to the nearest colors of the depth of
+/// the stream, by perceptual distance.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorDepth {
+    /// The 16 named colors.
+    Ansi16,
+    /// The 256 color palette.
+    Ansi256,
+    /// 24 bit RGB colors.
+    #[default]
+    TrueColor,
+}
+
 /// Data structure used to do fast ansi escape write operations.
 /// It implements many methods and traits which makes easier to format text.
 /// An internal buffer can be preallocated, which avoids allocation using write operations.
+#[cfg(feature = "std")]
 #[derive(Debug)]
 pub struct AnsiEscapeStream<W: Write> {
-    buffer: BufWriter<W>,
+    buffer: StreamBuffer<W>,
+    encoding: ColorEncoding,
+    theme: Theme,
+    colors: ColorAdjust,
 }
 
+#[cfg(feature = "std")]
 impl<W: Write> AnsiEscapeStream<W> {
     /// Initializes an AnsiEscapeStream.\
     /// writer is any struct which can implement Write trait.
     pub fn new(writer: W) -> Self {
+        Self::with_capacity(buffer::DEFAULT_CAPACITY, writer)
+    }
+
+    /// Initializes an AnsiEscapeStream whose buffer holds up to capacity bytes before
+    /// writing to writer.
+    pub fn with_capacity(capacity: usize, writer: W) -> Self {
         Self {
-            buffer: BufWriter::new(writer),
+            buffer: StreamBuffer::with_capacity(capacity, writer),
+            encoding: ColorEncoding::default(),
+            theme: Theme::default(),
+            colors: ColorAdjust::default(),
         }
     }
 
+    /// Returns the flush policy of the buffer.
+    pub fn flush_policy(&self) -> FlushPolicy {
+        self.buffer.flush_policy()
+    }
+
+    /// Set when the buffer is flushed to the writer, besides when it is full. Use
+    /// `FlushPolicy::Line` or `FlushPolicy::Interval` for interactive output, like progress bars.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ansistream::{AnsiEscapeStream, FlushPolicy};
+    ///
+    /// let mut astream = AnsiEscapeStream::new(Vec::<u8>::new());
+    /// astream.set_flush_policy(FlushPolicy::Line);
+    /// astream.write_string("10%").unwrap();
+    /// assert!(astream.get_ref().is_empty());
+    /// astream.write_string("\n").unwrap();
+    /// assert_eq!(b"10%\n", astream.get_ref().as_slice());
+    /// ```
+    pub fn set_flush_policy(&mut self, policy: FlushPolicy) {
+        self.buffer.set_flush_policy(policy);
+    }
+
+    /// Returns the encoding used to write rich colors.
+    pub fn color_encoding(&self) -> ColorEncoding {
+        self.encoding
+    }
+
+    /// Set the encoding used by all rich color operations of this stream.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ansistream::{AnsiEscapeStream, ColorEncoding};
+    /// use std::io::Cursor;
+    ///
+    /// let buffer = Cursor::new(Vec::<u8>::new());
+    /// let mut astream = AnsiEscapeStream::new(buffer);
+    /// astream.set_color_encoding(ColorEncoding::ColonWithColorSpace);
+    /// astream.write_text_fcrgb(255, 128, 0, "").unwrap();
+    /// assert_eq!(b"\x1b[38:2::255:128:0m", astream.buffer());
+    /// ```
+    pub fn set_color_encoding(&mut self, encoding: ColorEncoding) {
+        self.encoding = encoding;
+    }
+
+    /// Returns the color depth used by gradients and adjusted colors.
+    pub fn color_depth(&self) -> ColorDepth {
+        self.colors.depth
+    }
+
+    /// Set the colors the terminal can show. Gradients, and colors changed by the minimum
+    /// contrast or the simulation, use the nearest colors of depth.
+    pub fn set_color_depth(&mut self, depth: ColorDepth) {
+        self.colors.depth = depth;
+    }
+
+    /// Returns the theme used by `write_role`.
+    pub fn theme(&self) -> &Theme {
+        &self.theme
+    }
+
+    /// Set the theme which styles the roles written by `write_role`.
+    pub fn set_theme(&mut self, theme: Theme) {
+        self.theme = theme;
+    }
+
+    /// Returns the color scheme of the terminal, used to check the contrast of colors.
+    pub fn color_scheme(&self) -> &ColorScheme {
+        &self.colors.scheme
+    }
+
+    /// Set the color scheme of the terminal, which gives the RGB values of named and default
+    /// colors when the contrast is checked.
+    pub fn set_color_scheme(&mut self, scheme: ColorScheme) {
+        self.colors.scheme = scheme;
+    }
+
+    /// Returns the minimum contrast ratio of foreground colors, if it is checked.
+    pub fn min_contrast(&self) -> Option<f32> {
+        self.colors.min_contrast
+    }
+
+    /// Set the minimum WCAG contrast ratio of foreground colors against their background, like
+    /// `WCAG_AA`, or `None` to write colors as they are. Colors with too little contrast get a
+    /// foreground of the same hue with another lightness, or the nearest color of the color
+    /// depth which has enough contrast. Like `set_cvd_simulation`, it applies to every color of
+    /// the stream but the `AnsiWrite` methods of the buffer. Default colors are resolved with
+    /// the color scheme.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ansistream::{AnsiEscapeStream, ColorDepth, BC_BLACK, FC_BLUE, WCAG_AA_LARGE};
+    /// use std::io::Cursor;
+    ///
+    /// let buffer = Cursor::new(Vec::<u8>::new());
+    /// let mut astream = AnsiEscapeStream::new(buffer);
+    /// astream.set_min_contrast(Some(WCAG_AA_LARGE));
+    /// astream.set_color_depth(ColorDepth::Ansi16);
+    /// // blue is too dark on black
+    /// astream.write_text_color(FC_BLUE, BC_BLACK, "info").unwrap();
+    /// assert_eq!(b"\x1b[94;40minfo\x1b[39;49m", astream.buffer());
+    /// ```
+    pub fn set_min_contrast(&mut self, ratio: Option<f32>) {
+        self.colors.min_contrast = ratio;
+    }
+
+    /// Returns the simulated color vision deficiency, if any.
+    pub fn cvd_simulation(&self) -> Option<Deficiency> {
+        self.colors.simulation
+    }
+
+    /// Write the colors of this stream as they look with a color vision deficiency, to preview
+    /// output, or `None` to write them as they are. It applies to the colors of attributes,
+    /// styles, roles, markup, gradients and the 256 and RGB color methods, but not to the
+    /// `AnsiWrite` methods of the buffer, like `write_color_256`. The simulated colors are the
+    /// nearest of the color depth, named colors have the RGB values of the color scheme, and
+    /// default colors are not changed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ansistream::{AnsiEscapeStream, ColorDepth, Deficiency, BC_DEFAULT, FC_GREEN};
+    /// use std::io::Cursor;
+    ///
+    /// let buffer = Cursor::new(Vec::<u8>::new());
+    /// let mut astream = AnsiEscapeStream::new(buffer);
+    /// astream.set_color_depth(ColorDepth::Ansi16);
+    /// astream.set_cvd_simulation(Some(Deficiency::Deuteranopia));
+    /// astream.write_text_color(FC_GREEN, BC_DEFAULT, "pass").unwrap();
+    /// // green looks brown
+    /// assert_eq!(b"\x1b[33;49mpass\x1b[39;49m", astream.buffer());
+    /// ```
+    pub fn set_cvd_simulation(&mut self, deficiency: Option<Deficiency>) {
+        self.colors.simulation = deficiency;
+    }
+
     /// Clear the internal buffer.\
-    /// The buffer position is updated to 0, and all data is cleared. The capacity remains the same.
-    // pub fn clear(&mut self) {
-    //     self.buffer.set_position(0);
-    //     self.buffer.get_mut().clear();
-    // }
+    /// All data which was not flushed is discarded. The capacity remains the same.
+    pub fn clear(&mut self) {
+        self.buffer.clear();
+    }
 
-    // /// Set the internal buffer position to 0.
-    // pub fn reset(&mut self) {
-    //     self.buffer.set_position(0);
-    // }
+    /// Discard the buffered data, like `clear`. The options of the stream are kept, see
+    /// `reset_options` to restore them, and `rollback` to discard only the data written after a
+    /// checkpoint.
+    #[deprecated(note = "use clear, which does the same")]
+    pub fn reset(&mut self) {
+        self.clear();
+    }
+
+    /// Restore the default color encoding, color depth, theme, color scheme, contrast,
+    /// simulation and flush policy, like a new stream over the same writer. The buffered data
+    /// is kept.
+    pub fn reset_options(&mut self) {
+        self.buffer.set_flush_policy(FlushPolicy::default());
+        self.encoding = ColorEncoding::default();
+        self.theme = Theme::default();
+        self.colors = ColorAdjust::default();
+    }
 
     /// Reset all ansi escape code attributes before this buffer position using ESC[0m.
     pub fn reset_all_attributes(&mut self) -> io::Result<()> {
-        self.buffer.write_all(&[ESC])?;
-        write!(self.buffer, "[0m")?;
-        Ok(())
+        self.buffer.reset_all_attributes()
     }
 
     /// Reset an attribute of type(FC, BC, TS). If the attribute is a foreground color, then
     /// reset it to the default foreground color, and so on.
     pub fn reset_attribute(&mut self, attr: u16) -> io::Result<()> {
-        match attr {
-            TS_BOLD => self.write_attribute(TS_NO_BOLD)?,
-            TS_DIM => self.write_attribute(TS_NO_DIM)?,
-            TS_ITALIC => self.write_attribute(TS_NO_ITALIC)?,
-            TS_UNDERLINE => self.write_attribute(TS_NO_UNDERLINE)?,
-            TS_BLINK => self.write_attribute(TS_NO_BLINK)?,
-            TS_OVERLINE => self.write_attribute(TS_NO_OVERLINE)?,
-            TS_INVERT => self.write_attribute(TS_NO_INVERT)?,
-            TS_HIDDEN => self.write_attribute(TS_NO_HIDDEN)?,
-            TS_STRIKE => self.write_attribute(TS_NO_STRIKE)?,
-            FC_RICH_COLORS => self.write_attribute(FC_DEFAULT)?,
-            BC_RICH_COLORS => self.write_attribute(BC_DEFAULT)?,
-            30..=37 | 90..=97 => self.write_attribute(FC_DEFAULT)?,
-            40..=47 | 100..=107 => self.write_attribute(BC_DEFAULT)?,
-            _ => self.write_attribute(TS_DEFAULT)?,
-        };
+        self.buffer.reset_attribute(attr)
+    }
 
-        Ok(())
+    /// Select one of the alternative fonts (SGR 11-19). Font 0 restores the primary font,
+    /// any value above 9 is clamped to the last alternative font.
+    pub fn write_font(&mut self, font: u8) -> io::Result<()> {
+        self.buffer.write_font(font)
     }
 
     /// Write a byte slice to stream.
@@ -210,9 +542,12 @@ impl<W: Write> AnsiEscapeStream<W> {
 
     /// Write an attribute to stream.
     pub fn write_attribute(&mut self, attr: u16) -> io::Result<()> {
-        self.buffer.write_all(&[ESC])?;
-        write!(self.buffer, "[{attr}m")?;
-        Ok(())
+        match self.adjust_attribute(attr, Color::from_code(attr)) {
+            Some(style) => {
+                encode::write_style(&mut IoSink(&mut self.buffer), &style, self.encoding)
+            }
+            None => self.buffer.write_attribute(attr),
+        }
     }
 
     /// Write a string to stream.
@@ -240,20 +575,14 @@ impl<W: Write> AnsiEscapeStream<W> {
     /// );
     /// ```
     pub fn write_text_fc_fmt(&mut self, color: u16, fmt: fmt::Arguments<'_>) -> io::Result<()> {
-        match color {
-            40..=47 | 100..=107 => {
-                self.write_attribute(color - 10)?;
-                write!(self.buffer, "{fmt}")?;
-                self.reset_attribute(color - 10)?;
-            }
-            _ => {
-                self.write_attribute(color)?;
-                write!(self.buffer, "{fmt}")?;
-                self.reset_attribute(color)?;
-            }
+        let code = match color {
+            40..=47 | 100..=107 => color - 10,
+            _ => color,
         };
-
-        Ok(())
+        match self.adjust_attribute(code, Color::from_code(code)) {
+            Some(style) => self.write_adjusted_fmt(&style, fmt),
+            None => self.buffer.write_text_fc_fmt(color, fmt),
+        }
     }
 
     /// Write a 16 formatted background color text to stream. The attribute is reseted at the end of operation.
@@ -277,20 +606,14 @@ impl<W: Write> AnsiEscapeStream<W> {
     ///
     /// ```
     pub fn write_text_bc_fmt(&mut self, color: u16, fmt: Arguments<'_>) -> io::Result<()> {
-        match color {
-            30..=37 | 90..=97 => {
-                self.write_attribute(color + 10)?;
-                write!(self.buffer, "{fmt}")?;
-                self.reset_attribute(color + 10)?;
-            }
-            _ => {
-                self.write_attribute(color)?;
-                write!(self.buffer, "{fmt}")?;
-                self.reset_attribute(color)?;
-            }
+        let code = match color {
+            30..=37 | 90..=97 => color + 10,
+            _ => color,
         };
-
-        Ok(())
+        match self.adjust_attribute(code, Color::from_code(code)) {
+            Some(style) => self.write_adjusted_fmt(&style, fmt),
+            None => self.buffer.write_text_bc_fmt(color, fmt),
+        }
     }
 
     /// Write 16 foregournd and background color text to stream. If the text is
@@ -302,13 +625,10 @@ impl<W: Write> AnsiEscapeStream<W> {
         background: u16,
         text: &str,
     ) -> io::Result<()> {
-        self.buffer.write_all(&[ESC])?;
-        write!(self.buffer, "[{foreground};{background}m{text}")?;
-        if !text.is_empty() {
-            self.reset_attribute(foreground)?;
-            self.reset_attribute(background)?;
+        match self.adjust_colors(foreground, background) {
+            Some(style) => self.write_adjusted(&style, text),
+            None => self.buffer.write_text_color(foreground, background, text),
         }
-        Ok(())
     }
 
     /// Write 16 foreground and background color formatted text to stream.
@@ -319,62 +639,92 @@ impl<W: Write> AnsiEscapeStream<W> {
         background: u16,
         fmt: fmt::Arguments<'_>,
     ) -> io::Result<()> {
-        self.buffer.write_all(&[ESC])?;
-        write!(self.buffer, "[{foreground};{background}m{fmt}")?;
-        self.reset_attribute(foreground)?;
-        self.reset_attribute(background)?;
-        Ok(())
+        match self.adjust_colors(foreground, background) {
+            Some(style) => self.write_adjusted_fmt(&style, fmt),
+            None => self
+                .buffer
+                .write_text_color_fmt(foreground, background, fmt),
+        }
     }
 
     /// Write 256 foreground color text to the stream. If the text is empty, the
     /// reset operation will not be performed.
     pub fn write_text_fc256(&mut self, color: u16, text: &str) -> io::Result<()> {
-        self.buffer.write_all(&[ESC])?;
-        write!(self.buffer, "[{FC_RICH_COLORS};5;{color}m{text}")?;
-        if !text.is_empty() {
-            self.reset_attribute(FC_RICH_COLORS)?;
+        let attr = FC_RICH_COLORS;
+        match self.adjust_attribute(attr, ansi256(color)) {
+            Some(style) => self.write_adjusted(&style, text),
+            None => encode::write_text_256(
+                &mut IoSink(&mut self.buffer),
+                attr,
+                color,
+                self.encoding,
+                text,
+            ),
         }
-        Ok(())
     }
 
     /// Write 256 foreground color formatted text to the stream. A partial reset operation of used codes
     /// will be performed.
     pub fn write_text_fc256_fmt(&mut self, color: u16, fmt: fmt::Arguments<'_>) -> io::Result<()> {
-        self.buffer.write_all(&[ESC])?;
-        write!(self.buffer, "[{FC_RICH_COLORS};5;{color}m{fmt}")?;
-        self.reset_attribute(FC_RICH_COLORS)?;
-        Ok(())
+        let attr = FC_RICH_COLORS;
+        match self.adjust_attribute(attr, ansi256(color)) {
+            Some(style) => self.write_adjusted_fmt(&style, fmt),
+            None => encode::write_text_256_fmt(
+                &mut IoSink(&mut self.buffer),
+                attr,
+                color,
+                self.encoding,
+                fmt,
+            ),
+        }
     }
 
     /// Write 256 background color text to the stream. If the text is empty, the
     /// reset operation will not be performed.
     pub fn write_text_bc256(&mut self, color: u16, text: &str) -> io::Result<()> {
-        self.buffer.write_all(&[ESC])?;
-        write!(self.buffer, "[{BC_RICH_COLORS};5;{color}m{text}")?;
-        if !text.is_empty() {
-            self.reset_attribute(BC_RICH_COLORS)?;
+        let attr = BC_RICH_COLORS;
+        match self.adjust_attribute(attr, ansi256(color)) {
+            Some(style) => self.write_adjusted(&style, text),
+            None => encode::write_text_256(
+                &mut IoSink(&mut self.buffer),
+                attr,
+                color,
+                self.encoding,
+                text,
+            ),
         }
-        Ok(())
     }
 
     /// Write 256 background color formatted text to the stream. A partial reset operation of used codes
     /// will be performed.
     pub fn write_text_bc256_fmt(&mut self, color: u16, fmt: fmt::Arguments<'_>) -> io::Result<()> {
-        self.buffer.write_all(&[ESC])?;
-        write!(self.buffer, "[{BC_RICH_COLORS};5;{color}m{fmt}")?;
-        self.reset_attribute(BC_RICH_COLORS)?;
-        Ok(())
+        let attr = BC_RICH_COLORS;
+        match self.adjust_attribute(attr, ansi256(color)) {
+            Some(style) => self.write_adjusted_fmt(&style, fmt),
+            None => encode::write_text_256_fmt(
+                &mut IoSink(&mut self.buffer),
+                attr,
+                color,
+                self.encoding,
+                fmt,
+            ),
+        }
     }
 
     /// Write RGB foreground color text to the stream. If the text is empty, the
     /// reset operation will not be performed.
     pub fn write_text_fcrgb(&mut self, r: u16, g: u16, b: u16, text: &str) -> io::Result<()> {
-        self.buffer.write_all(&[ESC])?;
-        write!(self.buffer, "[{FC_RICH_COLORS};2;{r};{g};{b}m{text}")?;
-        if !text.is_empty() {
-            self.reset_attribute(FC_RICH_COLORS)?;
+        let attr = FC_RICH_COLORS;
+        match self.adjust_attribute(attr, rgb(r, g, b)) {
+            Some(style) => self.write_adjusted(&style, text),
+            None => encode::write_text_rgb(
+                &mut IoSink(&mut self.buffer),
+                attr,
+                (r, g, b),
+                self.encoding,
+                text,
+            ),
         }
-        Ok(())
     }
 
     /// Write RGB formatted foreground color text to the stream. A partial reset operation
@@ -386,21 +736,33 @@ impl<W: Write> AnsiEscapeStream<W> {
         b: u16,
         fmt: fmt::Arguments<'_>,
     ) -> io::Result<()> {
-        self.buffer.write_all(&[ESC])?;
-        write!(self.buffer, "[{FC_RICH_COLORS};2;{r};{g};{b}m{fmt}")?;
-        self.reset_attribute(FC_RICH_COLORS)?;
-        Ok(())
+        let attr = FC_RICH_COLORS;
+        match self.adjust_attribute(attr, rgb(r, g, b)) {
+            Some(style) => self.write_adjusted_fmt(&style, fmt),
+            None => encode::write_text_rgb_fmt(
+                &mut IoSink(&mut self.buffer),
+                attr,
+                (r, g, b),
+                self.encoding,
+                fmt,
+            ),
+        }
     }
 
     /// Write RGB background color text to the stream. If the text is empty, the
     /// reset operation will not be performed.
     pub fn write_text_bcrgb(&mut self, r: u16, g: u16, b: u16, text: &str) -> io::Result<()> {
-        self.buffer.write_all(&[ESC])?;
-        write!(self.buffer, "[{BC_RICH_COLORS};2;{r};{g};{b}m{text}")?;
-        if !text.is_empty() {
-            self.reset_attribute(BC_RICH_COLORS)?;
+        let attr = BC_RICH_COLORS;
+        match self.adjust_attribute(attr, rgb(r, g, b)) {
+            Some(style) => self.write_adjusted(&style, text),
+            None => encode::write_text_rgb(
+                &mut IoSink(&mut self.buffer),
+                attr,
+                (r, g, b),
+                self.encoding,
+                text,
+            ),
         }
-        Ok(())
     }
 
     /// Write formatted RGB background color text to the stream. A partial reset operation is performed.
@@ -411,27 +773,337 @@ impl<W: Write> AnsiEscapeStream<W> {
         b: u16,
         fmt: fmt::Arguments<'_>,
     ) -> io::Result<()> {
-        self.buffer.write_all(&[ESC])?;
-        write!(self.buffer, "[{BC_RICH_COLORS};2;{r};{g};{b}m{fmt}")?;
-        self.reset_attribute(BC_RICH_COLORS)?;
+        let attr = BC_RICH_COLORS;
+        match self.adjust_attribute(attr, rgb(r, g, b)) {
+            Some(style) => self.write_adjusted_fmt(&style, fmt),
+            None => encode::write_text_rgb_fmt(
+                &mut IoSink(&mut self.buffer),
+                attr,
+                (r, g, b),
+                self.encoding,
+                fmt,
+            ),
+        }
+    }
+
+    /// Write the attributes and colors of a style to stream as a single SGR sequence.
+    pub fn write_style(&mut self, style: &Style) -> io::Result<()> {
+        let style = self.colors.style(style);
+        encode::write_style(&mut IoSink(&mut self.buffer), &style, self.encoding)
+    }
+
+    /// Reset only the attributes and colors used by a style.
+    pub fn reset_style(&mut self, style: &Style) -> io::Result<()> {
+        let style = self.colors.style(style);
+        self.buffer.reset_style(&style)
+    }
+
+    /// Write styled text to stream. If the text is empty, the reset operation will not be performed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ansistream::{AnsiEscapeStream, Color, Style};
+    /// use std::io::Cursor;
+    ///
+    /// let buffer = Cursor::new(Vec::<u8>::new());
+    /// let mut astream = AnsiEscapeStream::new(buffer);
+    /// let warning = Style::new().fg(Color::Yellow).bold();
+    /// astream.write_text_style(&warning, "warning").unwrap();
+    /// assert_eq!(b"\x1b[1;93mwarning\x1b[22;39m", astream.buffer());
+    /// ```
+    pub fn write_text_style(&mut self, style: &Style, text: &str) -> io::Result<()> {
+        let style = self.colors.style(style);
+        self.write_adjusted(&style, text)
+    }
+
+    /// Write styled formatted text to stream. The style is reseted at the end of operation.
+    pub fn write_text_style_fmt(
+        &mut self,
+        style: &Style,
+        fmt: fmt::Arguments<'_>,
+    ) -> io::Result<()> {
+        let style = self.colors.style(style);
+        self.write_adjusted_fmt(&style, fmt)
+    }
+
+    /// Write text with a style which was already adjusted.
+    fn write_adjusted(&mut self, style: &Style, text: &str) -> io::Result<()> {
+        encode::write_text_style(&mut IoSink(&mut self.buffer), style, self.encoding, text)
+    }
+
+    /// Write formatted text with a style which was already adjusted.
+    fn write_adjusted_fmt(&mut self, style: &Style, fmt: fmt::Arguments<'_>) -> io::Result<()> {
+        encode::write_text_style_fmt(&mut IoSink(&mut self.buffer), style, self.encoding, fmt)
+    }
+
+    /// Returns the style of 16 color codes if their colors are changed by the color options.
+    fn adjust_colors(&mut self, foreground: u16, background: u16) -> Option<Style> {
+        let style = Style::new()
+            .fg(Color::from_code(foreground)?)
+            .bg(Color::from_code(background)?);
+        self.colors.changed(style)
+    }
+
+    /// Returns the style of a color attribute, like FC_RED, or FC_RICH_COLORS with its color,
+    /// if the color is changed by the color options. Default colors are written as they are.
+    fn adjust_attribute(&mut self, attr: u16, color: Option<Color>) -> Option<Style> {
+        let color = color.filter(|&color| color != Color::Default)?;
+        let style = match attr {
+            FC_RICH_COLORS | 30..=37 | 90..=97 => Style::new().fg(color),
+            _ => Style::new().bg(color),
+        };
+        self.colors.changed(style)
+    }
+
+    /// Write text with the style of a role in the theme of the stream. If the text is empty, the
+    /// reset operation will not be performed.
+    pub fn write_role(&mut self, role: Role, text: &str) -> io::Result<()> {
+        let style = self.theme.style(role);
+        self.write_text_style(&style, text)
+    }
+
+    /// Write formatted text with the style of a role in the theme of the stream. The style is
+    /// reseted at the end of operation.
+    pub fn write_role_fmt(&mut self, role: Role, fmt: fmt::Arguments<'_>) -> io::Result<()> {
+        let style = self.theme.style(role);
+        self.write_text_style_fmt(&style, fmt)
+    }
+
+    /// Write a path with the style `ls` gives to its file name, reading its metadata.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ansistream::{AnsiEscapeStream, LsColors};
+    /// use std::{io::Cursor, path::Path};
+    ///
+    /// let buffer = Cursor::new(Vec::<u8>::new());
+    /// let mut astream = AnsiEscapeStream::new(buffer);
+    /// let colors = LsColors::parse("mi=01;31").unwrap();
+    /// astream.write_path(&colors, Path::new("missing.txt")).unwrap();
+    /// assert_eq!(b"\x1b[1;31mmissing.txt\x1b[22;39m", astream.buffer());
+    /// ```
+    pub fn write_path(&mut self, colors: &LsColors, path: &Path) -> io::Result<()> {
+        let style = colors.style_for_path(path).unwrap_or_default();
+        self.write_text_style(&style, &path.to_string_lossy())
+    }
+
+    /// Write style markup to stream, like `[bold red]error:[/] text`. The markup is validated
+    /// before anything is written, see `Markup` for its syntax.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ansistream::AnsiEscapeStream;
+    /// use std::io::Cursor;
+    ///
+    /// let buffer = Cursor::new(Vec::<u8>::new());
+    /// let mut astream = AnsiEscapeStream::new(buffer);
+    /// astream.write_markup("[bold red]error:[/] file").unwrap();
+    /// assert_eq!(b"\x1b[1;31merror:\x1b[22;39m file", astream.buffer());
+    /// assert!(astream.write_markup("[bold]x[/red]").is_err());
+    /// ```
+    pub fn write_markup(&mut self, markup: &str) -> Result<()> {
+        let colors = &mut self.colors;
+        markup::write_markup(
+            &mut IoSink(&mut self.buffer),
+            markup,
+            self.encoding,
+            true,
+            &mut |style| colors.style(style),
+        )?;
+        Ok(())
+    }
+
+    /// Write text with a foreground gradient, one color per grapheme. Only the foreground color
+    /// is reset at the end.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ansistream::{AnsiEscapeStream, ColorDepth, Gradient};
+    /// use std::io::Cursor;
+    ///
+    /// let buffer = Cursor::new(Vec::<u8>::new());
+    /// let mut astream = AnsiEscapeStream::new(buffer);
+    /// let gradient = Gradient::new([(255, 0, 0), (0, 0, 255)]);
+    /// astream.write_text_fc_gradient(&gradient, "ab").unwrap();
+    /// assert_eq!(
+    ///     b"\x1b[38;2;255;0;0ma\x1b[38;2;0;0;255mb\x1b[39m",
+    ///     astream.buffer()
+    /// );
+    /// astream.clear();
+    /// // the same gradient on a 16 color terminal
+    /// astream.set_color_depth(ColorDepth::Ansi16);
+    /// astream.write_text_fc_gradient(&gradient, "ab").unwrap();
+    /// assert_eq!(b"\x1b[91ma\x1b[34mb\x1b[39m", astream.buffer());
+    /// ```
+    pub fn write_text_fc_gradient(&mut self, gradient: &Gradient, text: &str) -> io::Result<()> {
+        self.write_gradient(text, Some(gradient), None)
+    }
+
+    /// Write text with a background gradient, one color per grapheme. Only the background color
+    /// is reset at the end.
+    pub fn write_text_bc_gradient(&mut self, gradient: &Gradient, text: &str) -> io::Result<()> {
+        self.write_gradient(text, None, Some(gradient))
+    }
+
+    /// Write text with a foreground and a background gradient, one color per grapheme. Only the
+    /// colors are reset at the end.
+    pub fn write_text_gradient(
+        &mut self,
+        foreground: &Gradient,
+        background: &Gradient,
+        text: &str,
+    ) -> io::Result<()> {
+        self.write_gradient(text, Some(foreground), Some(background))
+    }
+
+    fn write_gradient(
+        &mut self,
+        text: &str,
+        foreground: Option<&Gradient>,
+        background: Option<&Gradient>,
+    ) -> io::Result<()> {
+        gradient::write_gradient(
+            &mut IoSink(&mut self.buffer),
+            text,
+            foreground,
+            background,
+            self.encoding,
+            &mut self.colors,
+        )
+    }
+
+    /// Write an attribute to stream, rejecting values that are not known SGR attributes
+    /// or that require additional parameters, like FC_RICH_COLORS.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ansistream::{AnsiEscapeStream, Error, FC_RICH_COLORS, TS_BOLD};
+    /// use std::io::Cursor;
+    ///
+    /// let buffer = Cursor::new(Vec::<u8>::new());
+    /// let mut astream = AnsiEscapeStream::new(buffer);
+    /// astream.try_write_attribute(TS_BOLD).unwrap();
+    /// assert!(matches!(
+    ///     astream.try_write_attribute(FC_RICH_COLORS),
+    ///     Err(Error::InvalidAttribute(38))
+    /// ));
+    /// assert_eq!(b"\x1b[1m", astream.buffer());
+    /// ```
+    pub fn try_write_attribute(&mut self, attr: u16) -> Result<()> {
+        self.write_attribute(Error::check_attribute(attr)?)?;
+        Ok(())
+    }
+
+    /// Write 256 foreground color text to the stream, rejecting indexes above 255.
+    pub fn try_write_text_fc256(&mut self, color: u16, text: &str) -> Result<()> {
+        self.write_text_fc256_u8(Error::check_color(color)?, text)?;
+        Ok(())
+    }
+
+    /// Write 256 background color text to the stream, rejecting indexes above 255.
+    pub fn try_write_text_bc256(&mut self, color: u16, text: &str) -> Result<()> {
+        self.write_text_bc256_u8(Error::check_color(color)?, text)?;
         Ok(())
     }
+
+    /// Write RGB foreground color text to the stream, rejecting components above 255.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ansistream::{AnsiEscapeStream, Error};
+    /// use std::io::Cursor;
+    ///
+    /// let buffer = Cursor::new(Vec::<u8>::new());
+    /// let mut astream = AnsiEscapeStream::new(buffer);
+    /// assert!(matches!(
+    ///     astream.try_write_text_fcrgb(999, 0, 0, "red"),
+    ///     Err(Error::InvalidColor(999))
+    /// ));
+    /// // nothing was written
+    /// assert!(astream.buffer().is_empty());
+    /// ```
+    pub fn try_write_text_fcrgb(&mut self, r: u16, g: u16, b: u16, text: &str) -> Result<()> {
+        let (r, g, b) = (
+            Error::check_color(r)?,
+            Error::check_color(g)?,
+            Error::check_color(b)?,
+        );
+        self.write_text_fcrgb_u8(r, g, b, text)?;
+        Ok(())
+    }
+
+    /// Write RGB background color text to the stream, rejecting components above 255.
+    pub fn try_write_text_bcrgb(&mut self, r: u16, g: u16, b: u16, text: &str) -> Result<()> {
+        let (r, g, b) = (
+            Error::check_color(r)?,
+            Error::check_color(g)?,
+            Error::check_color(b)?,
+        );
+        self.write_text_bcrgb_u8(r, g, b, text)?;
+        Ok(())
+    }
+
+    /// Write 256 foreground color text to the stream. The index type makes invalid colors
+    /// unrepresentable. If the text is empty, the reset operation will not be performed.
+    pub fn write_text_fc256_u8(&mut self, color: u8, text: &str) -> io::Result<()> {
+        self.write_text_fc256(u16::from(color), text)
+    }
+
+    /// Write 256 background color text to the stream. The index type makes invalid colors
+    /// unrepresentable. If the text is empty, the reset operation will not be performed.
+    pub fn write_text_bc256_u8(&mut self, color: u8, text: &str) -> io::Result<()> {
+        self.write_text_bc256(u16::from(color), text)
+    }
+
+    /// Write RGB foreground color text to the stream. The component types make invalid
+    /// colors unrepresentable. If the text is empty, the reset operation will not be performed.
+    pub fn write_text_fcrgb_u8(&mut self, r: u8, g: u8, b: u8, text: &str) -> io::Result<()> {
+        self.write_text_fcrgb(r.into(), g.into(), b.into(), text)
+    }
+
+    /// Write RGB background color text to the stream. The component types make invalid
+    /// colors unrepresentable. If the text is empty, the reset operation will not be performed.
+    pub fn write_text_bcrgb_u8(&mut self, r: u8, g: u8, b: u8, text: &str) -> io::Result<()> {
+        self.write_text_bcrgb(r.into(), g.into(), b.into(), text)
+    }
 }
 
+/// Returns the color of a 256 color index, if it is valid.
+#[cfg(feature = "std")]
+fn ansi256(color: u16) -> Option<Color> {
+    u8::try_from(color).ok().map(Color::Ansi256)
+}
+
+/// Returns the color of RGB components, if they are valid.
+#[cfg(feature = "std")]
+fn rgb(r: u16, g: u16, b: u16) -> Option<Color> {
+    let component = |c: u16| u8::try_from(c).ok();
+    Some(Color::Rgb(component(r)?, component(g)?, component(b)?))
+}
+
+#[cfg(feature = "std")]
 impl<W: Write> Deref for AnsiEscapeStream<W> {
-    type Target = BufWriter<W>;
+    type Target = StreamBuffer<W>;
 
     fn deref(&self) -> &Self::Target {
         &self.buffer
     }
 }
 
+#[cfg(feature = "std")]
 impl<W: Write> DerefMut for AnsiEscapeStream<W> {
     fn deref_mut(&mut self) -> &mut Self::Target {
         &mut self.buffer
     }
 }
 
+#[cfg(feature = "std")]
 impl<T, W: Write> AsMut<T> for AnsiEscapeStream<W>
 where
     <AnsiEscapeStream<W> as Deref>::Target: AsMut<T>,
@@ -441,6 +1113,7 @@ where
     }
 }
 
+#[cfg(feature = "std")]
 impl<T, W: Write> AsRef<T> for AnsiEscapeStream<W>
 where
     T: ?Sized,
@@ -451,7 +1124,7 @@ where
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "std"))]
 mod tests {
     use std::io::{Cursor, Write};
 
@@ -503,6 +1176,57 @@ mod tests {
         assert_eq!(&[0x1b, 0x5b, 0x33, 0x39, 0x6d], vec);
     }
 
+    #[test]
+    fn test_reset_rare_attributes() {
+        let c = Cursor::new(Vec::<u8>::new());
+        let mut astream = AnsiEscapeStream::new(c);
+        astream.reset_attribute(TS_FONT_3).unwrap();
+        astream.reset_attribute(TS_FRAKTUR).unwrap();
+        astream.reset_attribute(TS_DOUBLE_UNDERLINE).unwrap();
+        astream.reset_attribute(TS_ENCIRCLED).unwrap();
+        astream.reset_attribute(TS_OVERLINE).unwrap();
+        astream.reset_attribute(TS_IDEOGRAM_STRESS).unwrap();
+        astream.reset_attribute(TS_SUBSCRIPT).unwrap();
+        assert_eq!(
+            b"\x1b[10m\x1b[23m\x1b[24m\x1b[54m\x1b[55m\x1b[65m\x1b[75m",
+            astream.buffer()
+        );
+    }
+
+    #[test]
+    fn test_reset_codes() {
+        for (attr, reset) in [
+            (TS_BOLD, 22),
+            (TS_DIM, 22),
+            (TS_ITALIC, 23),
+            (TS_OVERLINE, 55),
+            (TS_FRAKTUR, 23),
+            (TS_DOUBLE_UNDERLINE, 24),
+            (TS_PROPORTIONAL_SPACING, 50),
+            // codes without a reset of their own reset everything
+            (TS_NO_PROPORTIONAL_SPACING, 0),
+        ] {
+            let c = Cursor::new(Vec::<u8>::new());
+            let mut astream = AnsiEscapeStream::new(c);
+            astream.reset_attribute(attr).unwrap();
+            assert_eq!(
+                format!("\x1b[{reset}m").as_bytes(),
+                astream.buffer(),
+                "{attr}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_write_font_function() {
+        let c = Cursor::new(Vec::<u8>::new());
+        let mut astream = AnsiEscapeStream::new(c);
+        astream.write_font(0).unwrap();
+        astream.write_font(4).unwrap();
+        astream.write_font(42).unwrap();
+        assert_eq!(b"\x1b[10m\x1b[14m\x1b[19m", astream.buffer());
+    }
+
     #[test]
     fn test_drefmut_implementation() {
         let c = Cursor::new(Vec::<u8>::new());
@@ -686,6 +1410,116 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_color_encoding() {
+        let c = Cursor::new(Vec::<u8>::new());
+        let mut astream = AnsiEscapeStream::new(c);
+        assert_eq!(ColorEncoding::Semicolon, astream.color_encoding());
+
+        astream.set_color_encoding(ColorEncoding::Colon);
+        astream.write_text_fc256(208, "").unwrap();
+        astream.write_text_bcrgb(1, 2, 3, "").unwrap();
+        assert_eq!(b"\x1b[38:5:208m\x1b[48:2:1:2:3m", astream.buffer());
+        astream.flush().unwrap();
+
+        astream.set_color_encoding(ColorEncoding::ColonWithColorSpace);
+        astream.write_text_bc256_fmt(16, format_args!("x")).unwrap();
+        astream
+            .write_text_fcrgb_fmt(1, 2, 3, format_args!("y"))
+            .unwrap();
+        assert_eq!(
+            b"\x1b[48:5:16mx\x1b[49m\x1b[38:2::1:2:3my\x1b[39m",
+            astream.buffer()
+        );
+    }
+
+    #[test]
+    fn test_checked_operations() {
+        let c = Cursor::new(Vec::<u8>::new());
+        let mut astream = AnsiEscapeStream::new(c);
+        assert!(matches!(
+            astream.try_write_text_fc256(256, "x"),
+            Err(Error::InvalidColor(256))
+        ));
+        assert!(matches!(
+            astream.try_write_text_bcrgb(0, 300, 0, "x"),
+            Err(Error::InvalidColor(300))
+        ));
+        assert!(matches!(
+            astream.try_write_attribute(BC_RICH_COLORS),
+            Err(Error::InvalidAttribute(48))
+        ));
+        assert!(astream.buffer().is_empty());
+
+        astream.try_write_text_bc256(255, "").unwrap();
+        astream.try_write_text_fcrgb(1, 2, 3, "").unwrap();
+        astream.try_write_attribute(TS_SUPERSCRIPT).unwrap();
+        assert_eq!(b"\x1b[48;5;255m\x1b[38;2;1;2;3m\x1b[73m", astream.buffer());
+    }
+
+    #[test]
+    fn test_u8_operations() {
+        let c = Cursor::new(Vec::<u8>::new());
+        let mut astream = AnsiEscapeStream::new(c);
+        astream.write_text_fc256_u8(208, "a").unwrap();
+        astream.write_text_bcrgb_u8(255, 0, 10, "b").unwrap();
+        assert_eq!(
+            b"\x1b[38;5;208ma\x1b[39m\x1b[48;2;255;0;10mb\x1b[49m",
+            astream.buffer()
+        );
+    }
+
+    #[test]
+    fn test_clear_and_reset() {
+        let mut astream = AnsiEscapeStream::new(Vec::<u8>::new());
+        astream.write_attribute(FC_RED).unwrap();
+        let checkpoint = astream.checkpoint();
+        astream.write_string("partial frame").unwrap();
+        assert!(astream.rollback(checkpoint));
+        assert_eq!(b"\x1b[31m", astream.take().as_slice());
+
+        astream.write_string("abc").unwrap();
+        astream.clear();
+        assert!(astream.buffer().is_empty());
+
+        astream.set_color_encoding(ColorEncoding::Colon);
+        astream.set_color_depth(ColorDepth::Ansi16);
+        astream.set_theme(Theme::NORD);
+        astream.set_flush_policy(FlushPolicy::Line);
+        astream.write_string("abc").unwrap();
+        astream.clear();
+        assert!(astream.buffer().is_empty());
+        assert_eq!(ColorEncoding::Colon, astream.color_encoding());
+        assert_eq!(&Theme::NORD, astream.theme());
+        astream.write_string("abc").unwrap();
+        astream.reset_options();
+        assert_eq!(b"abc", astream.buffer());
+        astream.clear();
+        assert_eq!(ColorEncoding::Semicolon, astream.color_encoding());
+        assert_eq!(ColorDepth::TrueColor, astream.color_depth());
+        assert_eq!(&Theme::BASIC, astream.theme());
+        assert_eq!(FlushPolicy::Manual, astream.flush_policy());
+        astream.flush().unwrap();
+        assert!(astream.get_ref().is_empty());
+    }
+
+    #[test]
+    fn test_write_text_style() {
+        let c = Cursor::new(Vec::<u8>::new());
+        let mut astream = AnsiEscapeStream::new(c);
+        astream.set_color_encoding(ColorEncoding::Colon);
+        let style = Style::new()
+            .fg(Color::Rgb(1, 2, 3))
+            .bg(Color::Blue)
+            .bold()
+            .dim();
+        astream.write_text_style(&style, "x").unwrap();
+        astream
+            .write_text_style_fmt(&Style::new(), format_args!("y"))
+            .unwrap();
+        assert_eq!(b"\x1b[1;2;38:2:1:2:3;44mx\x1b[22;39;49my", astream.buffer());
+    }
+
     #[test]
     fn test_write_text_color_fmt() {
         let c = Cursor::new(Vec::<u8>::new());
@@ -775,4 +1609,104 @@ mod tests {
             astream.buffer()
         );
     }
+
+    #[test]
+    fn test_min_contrast() {
+        let c = Cursor::new(Vec::<u8>::new());
+        let mut astream = AnsiEscapeStream::new(c);
+        astream.set_min_contrast(Some(WCAG_AA));
+        // enough contrast, written as it is
+        let style = Style::new().fg(Color::Black).bg(Color::White);
+        astream.write_text_style(&style, "x").unwrap();
+        assert_eq!(b"\x1b[30;107mx\x1b[39;49m", astream.buffer());
+        astream.clear();
+        // the default foreground of the scheme is too light on white
+        let style = Style::new().bg(Color::White);
+        astream.write_text_style(&style, "x").unwrap();
+        let text = String::from_utf8_lossy(astream.buffer()).into_owned();
+        assert!(text.starts_with("\x1b[38;2;"));
+        assert!(text.ends_with("x\x1b[39;49m"));
+        astream.reset_options();
+        assert_eq!(None, astream.min_contrast());
+    }
+
+    #[test]
+    fn test_min_contrast_of_every_write() {
+        let c = Cursor::new(Vec::<u8>::new());
+        let mut astream = AnsiEscapeStream::new(c);
+        astream.set_min_contrast(Some(WCAG_AA_LARGE));
+        astream.set_color_depth(ColorDepth::Ansi16);
+        // blue is too dark on the black background of the scheme
+        astream.write_text_fc256(4, "x").unwrap();
+        assert_eq!(b"\x1b[94mx\x1b[39m", astream.buffer());
+        astream.clear();
+        astream.write_text_fcrgb(0, 0, 238, "x").unwrap();
+        assert_eq!(b"\x1b[94mx\x1b[39m", astream.buffer());
+        astream.clear();
+        astream.write_markup("[blue on black]x[/]").unwrap();
+        assert_eq!(b"\x1b[94;40mx\x1b[39;49m", astream.buffer());
+        astream.clear();
+        astream
+            .write_text_fc_gradient(&Gradient::new([(0, 0, 238)]), "ab")
+            .unwrap();
+        assert_eq!(b"\x1b[94mab\x1b[39m", astream.buffer());
+    }
+
+    #[test]
+    fn test_cvd_simulation() {
+        let c = Cursor::new(Vec::<u8>::new());
+        let mut astream = AnsiEscapeStream::new(c);
+        astream.set_cvd_simulation(Some(Deficiency::Protanopia));
+        let (r, g, b) = Deficiency::Protanopia.simulate((255, 0, 0));
+        let style = Style::new().fg(Color::Rgb(255, 0, 0)).bold();
+        astream.write_text_style(&style, "x").unwrap();
+        let expected = format!("\x1b[1;38;2;{r};{g};{b}mx\x1b[22;39m");
+        assert_eq!(expected.as_bytes(), astream.buffer());
+        astream.clear();
+        // default colors are kept
+        astream.write_text_style(&Style::new().bold(), "x").unwrap();
+        assert_eq!(b"\x1b[1mx\x1b[22m", astream.buffer());
+        astream.reset_options();
+        assert_eq!(None, astream.cvd_simulation());
+    }
+
+    #[test]
+    fn test_cvd_simulation_of_every_write() {
+        let c = Cursor::new(Vec::<u8>::new());
+        let mut astream = AnsiEscapeStream::new(c);
+        let deficiency = Deficiency::Protanopia;
+        astream.set_cvd_simulation(Some(deficiency));
+        let simulate = |rgb| {
+            let (r, g, b) = deficiency.simulate(rgb);
+            format!("{r};{g};{b}")
+        };
+        astream.write_text_fc256(196, "x").unwrap();
+        let expected = format!("\x1b[38;2;{}mx\x1b[39m", simulate(XTERM_PALETTE[196]));
+        assert_eq!(expected.as_bytes(), astream.buffer());
+        astream.clear();
+        astream.write_text_bcrgb(255, 0, 0, "x").unwrap();
+        let expected = format!("\x1b[48;2;{}mx\x1b[49m", simulate((255, 0, 0)));
+        assert_eq!(expected.as_bytes(), astream.buffer());
+        astream.clear();
+        astream.write_markup("[#ff0000]a[bold]b[/bold][/]").unwrap();
+        let red = simulate((255, 0, 0));
+        let expected = format!("\x1b[38;2;{red}ma\x1b[1mb\x1b[22;39m\x1b[38;2;{red}m\x1b[39m");
+        assert_eq!(expected.as_bytes(), astream.buffer());
+        astream.clear();
+        // the code is written as the color the method is named after
+        astream
+            .write_text_fc_fmt(BC_RED, format_args!("x"))
+            .unwrap();
+        astream
+            .write_text_bc_fmt(FC_RED, format_args!("x"))
+            .unwrap();
+        let red = simulate(XTERM_PALETTE[1]);
+        let expected = format!("\x1b[38;2;{red}mx\x1b[39m\x1b[48;2;{red}mx\x1b[49m");
+        assert_eq!(expected.as_bytes(), astream.buffer());
+        astream.clear();
+        // colors which can not be simulated are written as they are
+        astream.write_text_fc256(300, "").unwrap();
+        astream.write_attribute(FC_DEFAULT).unwrap();
+        assert_eq!(b"\x1b[38;5;300m\x1b[39m", astream.buffer());
+    }
 }
@@ -0,0 +1,515 @@
+//! Style markup, like `[bold red]error:[/] file [link=https://x]name[/link]`.
+//!
+//! A tag opens a style with space separated words: attributes like `bold` or `strike`, colors
+//! like `red`, `light_blue`, `208` or `#ff8800`, and background colors preceded by `on`. A
+//! `[link=url]` tag opens a hyperlink. `[/]` closes the last tag, and `[/words]` closes it
+//! checking that it is the expected one. Tags still open at the end are closed. Use `\[` for a
+//! literal bracket and `\\` for a literal backslash.
+//!
+//! Markup is validated entirely before anything is written, so malformed markup never leaves
+//! partial output or open escape codes behind.
+
+use core::{
+    convert::Infallible,
+    fmt::{self, Display},
+};
+
+use crate::{
+    colors_enabled,
+    encode::{self, FmtSink, Sink},
+    Attributes, Color, ColorEncoding, Style,
+};
+
+/// Maximum number of tags open at the same time.
+pub const MAX_MARKUP_DEPTH: usize = 16;
+
+/// What is wrong with a malformed markup.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MarkupErrorKind {
+    /// A `[` without the `]` which ends the tag.
+    UnterminatedTag,
+    /// A tag without words, `[]`.
+    EmptyTag,
+    /// A word which is not an attribute or a color.
+    UnknownWord,
+    /// `on` without a background color after it.
+    MissingBackground,
+    /// A `[link=]` tag without url.
+    MissingUrl,
+    /// A link url with a control character, which could end the hyperlink sequence.
+    InvalidUrl,
+    /// A closing tag without an open tag.
+    UnexpectedClose,
+    /// A closing tag which does not match the last open tag.
+    MismatchedClose,
+    /// More than `MAX_MARKUP_DEPTH` tags are open.
+    TooDeep,
+}
+
+/// A malformed markup, with the byte position of the problem.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MarkupError {
+    kind: MarkupErrorKind,
+    position: usize,
+}
+
+impl MarkupError {
+    /// Returns what is wrong with the markup.
+    pub fn kind(&self) -> MarkupErrorKind {
+        self.kind
+    }
+
+    /// Returns the byte position of the problem in the markup.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+}
+
+impl Display for MarkupError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = match self.kind {
+            MarkupErrorKind::UnterminatedTag => "tag is not terminated by ]",
+            MarkupErrorKind::EmptyTag => "empty tag",
+            MarkupErrorKind::UnknownWord => "unknown attribute or color",
+            MarkupErrorKind::MissingBackground => "expected a background color after on",
+            MarkupErrorKind::MissingUrl => "link without url",
+            MarkupErrorKind::InvalidUrl => "control character in link url",
+            MarkupErrorKind::UnexpectedClose => "closing tag without open tag",
+            MarkupErrorKind::MismatchedClose => "closing tag does not match the open tag",
+            MarkupErrorKind::TooDeep => "too many nested tags",
+        };
+        write!(f, "{message} at byte {}", self.position)
+    }
+}
+
+impl core::error::Error for MarkupError {}
+
+/// A validated style markup. Displaying it writes the text with the escape codes of its tags,
+/// or only the text when colors are disabled.
+///
+/// # Examples
+///
+/// ```
+/// use ansistream::Markup;
+///
+/// let markup = Markup::parse("[bold red]error:[/] \\[1/2]").unwrap();
+/// assert_eq!("\x1b[1;31merror:\x1b[22;39m [1/2]", markup.to_string());
+///
+/// let err = Markup::parse("[bold red]error:[/blue]").unwrap_err();
+/// assert_eq!("closing tag does not match the open tag at byte 16", err.to_string());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Markup<'a> {
+    text: &'a str,
+}
+
+impl<'a> Markup<'a> {
+    /// Validate the markup.
+    pub fn parse(text: &'a str) -> Result<Self, MarkupError> {
+        validate(text)?;
+        Ok(Markup { text })
+    }
+
+    /// Returns the markup text.
+    pub fn as_str(&self) -> &'a str {
+        self.text
+    }
+
+    /// Escape text so it is displayed as is inside markup, like a path interpolated with
+    /// `format!`.
+    pub fn escape(text: &str) -> impl Display + '_ {
+        Escaped(text)
+    }
+}
+
+impl Display for Markup<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let styled = colors_enabled();
+        let encoding = ColorEncoding::Semicolon;
+        render(&mut FmtSink(f), self.text, encoding, styled, &mut keep).map_err(|err| match err {
+            Failure::Sink(err) => err,
+            Failure::Markup(_) => fmt::Error,
+        })
+    }
+}
+
+struct Escaped<'a>(&'a str);
+
+impl Display for Escaped<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for c in self.0.chars() {
+            if c == '[' || c == '\\' {
+                f.write_str("\\")?;
+            }
+            write!(f, "{c}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Error of a render, from the markup or from the sink.
+pub(crate) enum Failure<E> {
+    Markup(MarkupError),
+    Sink(E),
+}
+
+impl<E> From<E> for Failure<E> {
+    fn from(err: E) -> Self {
+        Failure::Sink(err)
+    }
+}
+
+impl<E> From<Failure<E>> for crate::Error
+where
+    crate::Error: From<E>,
+{
+    fn from(err: Failure<E>) -> Self {
+        match err {
+            Failure::Markup(err) => err.into(),
+            Failure::Sink(err) => err.into(),
+        }
+    }
+}
+
+/// Sink which discards everything, used to validate markup.
+struct Discard;
+
+impl Sink for Discard {
+    type Error = Infallible;
+
+    fn put(&mut self, _: &str) -> Result<(), Infallible> {
+        Ok(())
+    }
+
+    fn put_escape(&mut self, _: &encode::Escape) -> Result<(), Infallible> {
+        Ok(())
+    }
+
+    fn put_fmt(&mut self, _: fmt::Arguments<'_>) -> Result<(), Infallible> {
+        Ok(())
+    }
+}
+
+pub(crate) fn validate(text: &str) -> Result<(), MarkupError> {
+    match render(
+        &mut Discard,
+        text,
+        ColorEncoding::Semicolon,
+        true,
+        &mut keep,
+    ) {
+        Err(Failure::Markup(err)) => Err(err),
+        _ => Ok(()),
+    }
+}
+
+/// Validate the markup, then write it. If styled is false, only the text is written. adjust
+/// returns the style which is shown for the style of the open tags.
+pub(crate) fn write_markup<S: Sink>(
+    s: &mut S,
+    text: &str,
+    encoding: ColorEncoding,
+    styled: bool,
+    adjust: &mut impl FnMut(&Style) -> Style,
+) -> Result<(), Failure<S::Error>> {
+    validate(text).map_err(Failure::Markup)?;
+    render(s, text, encoding, styled, adjust)
+}
+
+/// Shows the styles of the tags as they are.
+pub(crate) fn keep(style: &Style) -> Style {
+    *style
+}
+
+/// An open tag.
+#[derive(Clone, Copy)]
+struct Frame<'a> {
+    tag: &'a str,
+    /// The style of the tag on top of the style of its parent.
+    style: Style,
+    /// The style which is shown, style adjusted.
+    shown: Style,
+    link: Option<&'a str>,
+}
+
+impl Frame<'_> {
+    const ROOT: Frame<'static> = Frame {
+        tag: "",
+        style: Style::new(),
+        shown: Style::new(),
+        link: None,
+    };
+}
+
+fn render<S: Sink>(
+    s: &mut S,
+    text: &str,
+    encoding: ColorEncoding,
+    styled: bool,
+    adjust: &mut impl FnMut(&Style) -> Style,
+) -> Result<(), Failure<S::Error>> {
+    let error = |kind, position| Failure::Markup(MarkupError { kind, position });
+    let mut stack = [Frame::ROOT; MAX_MARKUP_DEPTH + 1];
+    let mut depth = 0;
+    let mut start = 0;
+    let bytes = text.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' if matches!(bytes.get(i + 1), Some(b'[' | b'\\')) => {
+                s.put(&text[start..i])?;
+                start = i + 1;
+                i += 2;
+            }
+            b'[' => {
+                s.put(&text[start..i])?;
+                let end = text[i..]
+                    .find(']')
+                    .map(|end| i + end)
+                    .ok_or(error(MarkupErrorKind::UnterminatedTag, i))?;
+                let tag = text[i + 1..end].trim();
+                if tag.is_empty() {
+                    return Err(error(MarkupErrorKind::EmptyTag, i));
+                }
+                if let Some(close) = tag.strip_prefix('/') {
+                    if depth == 0 {
+                        return Err(error(MarkupErrorKind::UnexpectedClose, i));
+                    }
+                    let open = stack[depth];
+                    let close = close.trim();
+                    let matches = close.is_empty()
+                        || close == open.tag
+                        || (close == "link" && open.tag.starts_with("link="));
+                    if !matches {
+                        return Err(error(MarkupErrorKind::MismatchedClose, i));
+                    }
+                    depth -= 1;
+                    if styled {
+                        close_frame(s, &open, &stack[depth], encoding)?;
+                    }
+                } else {
+                    if depth == MAX_MARKUP_DEPTH {
+                        return Err(error(MarkupErrorKind::TooDeep, i));
+                    }
+                    let parent = stack[depth];
+                    let frame = if let Some(url) = tag.strip_prefix("link=") {
+                        let url = url.trim();
+                        if url.is_empty() {
+                            return Err(error(MarkupErrorKind::MissingUrl, i));
+                        }
+                        if let Some(at) = url.find(|c: char| c.is_ascii_control()) {
+                            let position = url.as_ptr() as usize - text.as_ptr() as usize + at;
+                            return Err(error(MarkupErrorKind::InvalidUrl, position));
+                        }
+                        if styled {
+                            encode::open_hyperlink(s, url)?;
+                        }
+                        Frame {
+                            tag,
+                            link: Some(url),
+                            ..parent
+                        }
+                    } else {
+                        let style = parse_words(text, tag).map_err(Failure::Markup)?;
+                        let patched = patch(parent.style, style);
+                        let shown = adjust(&patched);
+                        if styled {
+                            encode::write_style(
+                                s,
+                                &opened(style, &shown, &parent.shown),
+                                encoding,
+                            )?;
+                        }
+                        Frame {
+                            tag,
+                            style: patched,
+                            shown,
+                            link: parent.link,
+                        }
+                    };
+                    depth += 1;
+                    stack[depth] = frame;
+                }
+                i = end + 1;
+                start = i;
+            }
+            _ => i += 1,
+        }
+    }
+    s.put(&text[start..])?;
+    while depth > 0 && styled {
+        depth -= 1;
+        close_frame(s, &stack[depth + 1], &stack[depth], encoding)?;
+    }
+    Ok(())
+}
+
+/// Close a tag, restoring the style and link of its parent.
+fn close_frame<S: Sink>(
+    s: &mut S,
+    open: &Frame<'_>,
+    parent: &Frame<'_>,
+    encoding: ColorEncoding,
+) -> Result<(), S::Error> {
+    if open.link != parent.link {
+        encode::close_hyperlink(s)?;
+        if let Some(url) = parent.link {
+            encode::open_hyperlink(s, url)?;
+        }
+    }
+    if open.shown != parent.shown {
+        encode::reset_style(s, &open.shown)?;
+        encode::write_style(s, &parent.shown, encoding)?;
+    }
+    Ok(())
+}
+
+/// Returns the style written when a tag opens: the style of the tag, with the colors which are
+/// shown, and the colors of the parent which the adjustment changed.
+fn opened(style: Style, shown: &Style, parent: &Style) -> Style {
+    let color = |own: Option<Color>, shown: Option<Color>, parent: Option<Color>| {
+        (own.is_some() || shown != parent).then_some(shown.unwrap_or(Color::Default))
+    };
+    Style {
+        foreground: color(style.foreground, shown.foreground, parent.foreground),
+        background: color(style.background, shown.background, parent.background),
+        ..style
+    }
+}
+
+/// Returns style with the colors and attributes of other on top.
+fn patch(style: Style, other: Style) -> Style {
+    Style {
+        foreground: other.foreground.or(style.foreground),
+        background: other.background.or(style.background),
+        attributes: style.attributes | other.attributes,
+        negated: style.negated | other.negated,
+    }
+}
+
+/// Parse the words of a tag. tag is a slice of text, used for error positions.
+fn parse_words(text: &str, tag: &str) -> Result<Style, MarkupError> {
+    let position = |word: &str| word.as_ptr() as usize - text.as_ptr() as usize;
+    let mut style = Style::new();
+    let mut words = tag.split_whitespace();
+    while let Some(word) = words.next() {
+        if word == "on" {
+            let color = words.next().ok_or(MarkupError {
+                kind: MarkupErrorKind::MissingBackground,
+                position: position(word),
+            })?;
+            style.background = Some(Color::from_word(color).ok_or(MarkupError {
+                kind: MarkupErrorKind::UnknownWord,
+                position: position(color),
+            })?);
+        } else if let Some(attr) = Attributes::from_name(word) {
+            style.attributes.insert(attr);
+        } else if let Some(color) = Color::from_word(word) {
+            style.foreground = Some(color);
+        } else {
+            return Err(MarkupError {
+                kind: MarkupErrorKind::UnknownWord,
+                position: position(word),
+            });
+        }
+    }
+    Ok(style)
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    fn render_string(text: &str) -> String {
+        let mut out = String::new();
+        let encoding = ColorEncoding::Semicolon;
+        write_markup(&mut FmtSink(&mut out), text, encoding, true, &mut keep)
+            .map_err(|_| ())
+            .unwrap();
+        out
+    }
+
+    fn error_of(text: &str) -> (MarkupErrorKind, usize) {
+        let err = Markup::parse(text).unwrap_err();
+        (err.kind(), err.position())
+    }
+
+    #[test]
+    fn test_render_tags() {
+        assert_eq!(
+            "\x1b[1;31merror:\x1b[22;39m file",
+            render_string("[bold red]error:[/] file")
+        );
+        assert_eq!(
+            "\x1b[48;5;236mx\x1b[49m",
+            render_string("[on 236]x[/on 236]")
+        );
+        // tags still open at the end are closed
+        assert_eq!("\x1b[38;2;255;136;0mx\x1b[39m", render_string("[#ff8800]x"));
+    }
+
+    #[test]
+    fn test_render_nested() {
+        assert_eq!(
+            "\x1b[1ma\x1b[31mb\x1b[22;39m\x1b[1mc\x1b[22m",
+            render_string("[bold]a[red]b[/red]c[/bold]")
+        );
+        assert_eq!(
+            "\x1b]8;;https://x\x1b\\\x1b[4mpath\x1b[24m\x1b]8;;\x1b\\",
+            render_string("[link=https://x][underline]path[/][/link]")
+        );
+        assert_eq!(
+            "\x1b]8;;x\x1b\\\x1b[1ma\x1b[22m\x1b]8;;\x1b\\b",
+            render_string("[link=x][bold]a[/bold][/link]b")
+        );
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!("[a] \\b \\", render_string("\\[a] \\\\b \\"));
+        let escaped = Markup::escape("C:\\[x]").to_string();
+        assert_eq!("C:\\\\\\[x]", escaped);
+        assert_eq!("C:\\[x]", render_string(&escaped));
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!((MarkupErrorKind::UnterminatedTag, 4), error_of("abc [bold"));
+        assert_eq!((MarkupErrorKind::EmptyTag, 0), error_of("[]"));
+        assert_eq!(
+            (MarkupErrorKind::UnknownWord, 6),
+            error_of("[bold purple]x")
+        );
+        assert_eq!(
+            (MarkupErrorKind::MissingBackground, 5),
+            error_of("[red on]x")
+        );
+        assert_eq!((MarkupErrorKind::UnknownWord, 8), error_of("[red on #12]x"));
+        assert_eq!((MarkupErrorKind::MissingUrl, 0), error_of("[link=]x"));
+        assert_eq!(
+            (MarkupErrorKind::InvalidUrl, 10),
+            error_of("[link=http\x1b]8;;evil]x")
+        );
+        assert_eq!((MarkupErrorKind::InvalidUrl, 6), error_of("[link=\x07]x"));
+        assert_eq!((MarkupErrorKind::InvalidUrl, 7), error_of("[link=a\x7f]x"));
+        // only the link tag closes with [/link], not the tags open inside it
+        assert_eq!(
+            (MarkupErrorKind::MismatchedClose, 22),
+            error_of("[link=http://x][bold]a[/link]b")
+        );
+        assert_eq!(
+            (MarkupErrorKind::MismatchedClose, 15),
+            error_of("[link=x][bold]a[/link]b[/bold]")
+        );
+        assert_eq!((MarkupErrorKind::UnexpectedClose, 1), error_of("x[/]"));
+        assert_eq!(
+            (MarkupErrorKind::MismatchedClose, 6),
+            error_of("[red]x[/blue]")
+        );
+        let deep = "[bold]".repeat(MAX_MARKUP_DEPTH + 1);
+        assert_eq!(
+            (MarkupErrorKind::TooDeep, 6 * MAX_MARKUP_DEPTH),
+            error_of(&deep)
+        );
+    }
+}
@@ -0,0 +1,132 @@
+//! RGB values of the xterm 256 color palette.
+
+/// A RGB color, as 8 bit sRGB components.
+pub type Rgb = (u8, u8, u8);
+
+/// The intensities of the 6 levels of each component of the color cube.
+pub const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+/// The RGB values of the xterm 256 color palette: the 16 named colors with the xterm defaults,
+/// the 6x6x6 color cube at 16-231, and 24 grays at 232-255, from 8 to 238 in steps of 10.
+/// The named colors change with the terminal theme, the other colors are the same in most
+/// terminals.
+pub const XTERM_PALETTE: [Rgb; 256] = xterm_palette();
+
+const fn xterm_palette() -> [Rgb; 256] {
+    let mut palette = [(0, 0, 0); 256];
+    let named = [
+        (0, 0, 0),
+        (205, 0, 0),
+        (0, 205, 0),
+        (205, 205, 0),
+        (0, 0, 238),
+        (205, 0, 205),
+        (0, 205, 205),
+        (229, 229, 229),
+        (127, 127, 127),
+        (255, 0, 0),
+        (0, 255, 0),
+        (255, 255, 0),
+        (92, 92, 255),
+        (255, 0, 255),
+        (0, 255, 255),
+        (255, 255, 255),
+    ];
+    let mut i = 0;
+    while i < 16 {
+        palette[i] = named[i];
+        i += 1;
+    }
+    while i < 232 {
+        let n = i - 16;
+        palette[i] = (
+            CUBE_LEVELS[n / 36],
+            CUBE_LEVELS[n / 6 % 6],
+            CUBE_LEVELS[n % 6],
+        );
+        i += 1;
+    }
+    while i < 256 {
+        let level = 8 + 10 * (i - 232) as u8;
+        palette[i] = (level, level, level);
+        i += 1;
+    }
+    palette
+}
+
+/// Returns the index of the color cube with levels 0-5 of each component. Levels above 5 are
+/// clamped.
+///
+/// # Examples
+///
+/// ```
+/// use ansistream::{xterm_cube, XTERM_PALETTE};
+///
+/// assert_eq!(208, xterm_cube(5, 2, 0));
+/// assert_eq!((255, 135, 0), XTERM_PALETTE[208]);
+/// ```
+pub const fn xterm_cube(r: u8, g: u8, b: u8) -> u8 {
+    16 + 36 * at_most(r, 5) + 6 * at_most(g, 5) + at_most(b, 5)
+}
+
+/// Returns the index of a step of the gray ramp, from 0, almost black, to 23, almost white.
+/// Steps above 23 are clamped.
+pub const fn xterm_gray(step: u8) -> u8 {
+    232 + at_most(step, 23)
+}
+
+const fn at_most(value: u8, max: u8) -> u8 {
+    if value > max {
+        max
+    } else {
+        value
+    }
+}
+
+/// Returns the palette index of a RGB value, if it is in the palette. Colors of the cube and
+/// of the gray ramp are preferred to the named colors, which change with the terminal theme.
+pub fn xterm_index((r, g, b): Rgb) -> Option<u8> {
+    let level = |c: u8| CUBE_LEVELS.iter().position(|&l| l == c).map(|l| l as u8);
+    if let (Some(r), Some(g), Some(b)) = (level(r), level(g), level(b)) {
+        return Some(xterm_cube(r, g, b));
+    }
+    if r == g && g == b && (8..=238).contains(&r) && (r - 8) % 10 == 0 {
+        return Some(xterm_gray((r - 8) / 10));
+    }
+    XTERM_PALETTE[..16]
+        .iter()
+        .position(|&c| c == (r, g, b))
+        .map(|i| i as u8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_palette() {
+        assert_eq!((205, 0, 0), XTERM_PALETTE[1]);
+        assert_eq!((0, 0, 0), XTERM_PALETTE[16]);
+        assert_eq!((95, 215, 255), XTERM_PALETTE[81]);
+        assert_eq!((255, 255, 255), XTERM_PALETTE[231]);
+        assert_eq!((8, 8, 8), XTERM_PALETTE[232]);
+        assert_eq!((238, 238, 238), XTERM_PALETTE[255]);
+        assert_eq!(16, xterm_cube(0, 0, 0));
+        assert_eq!(231, xterm_cube(9, 9, 9));
+        assert_eq!(232, xterm_gray(0));
+        assert_eq!(255, xterm_gray(30));
+    }
+
+    #[test]
+    fn test_index() {
+        for index in 16..=255 {
+            assert_eq!(Some(index), xterm_index(XTERM_PALETTE[usize::from(index)]));
+        }
+        // named colors which are not in the cube
+        assert_eq!(Some(1), xterm_index((205, 0, 0)));
+        assert_eq!(Some(12), xterm_index((92, 92, 255)));
+        // white is also a cube color
+        assert_eq!(Some(231), xterm_index((255, 255, 255)));
+        assert_eq!(None, xterm_index((1, 2, 3)));
+    }
+}
@@ -0,0 +1,393 @@
+//! Writer adapter which colors text with cycling rainbow hues, like lolcat.
+
+use std::{
+    f32::consts::PI,
+    io::{self, Write},
+    str,
+};
+
+use crate::{
+    adjust::ColorAdjust,
+    encode::{self, IoSink},
+    gradient::extends,
+    Color, ColorDepth, ColorEncoding, Deficiency, Style, FC_DEFAULT,
+};
+
+/// Where the adapter is in an escape sequence of the input, which is passed through untouched.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Text,
+    Escape,
+    Csi,
+    Osc,
+}
+
+/// A writer which colors the text written to it with rainbow colors, then writes it to
+/// another writer. The red, green and blue components follow sine waves along the characters
+/// of a line, and each line starts a step further, which gives diagonal stripes.
+///
+/// New lines reset the foreground color, escape sequences of the input are passed through,
+/// and characters split between writes are kept until they are complete. Call `finish` after
+/// the last write, to reset the color of an unterminated line.
+///
+/// # Examples
+///
+/// ```
+/// use ansistream::Rainbow;
+/// use std::io::Write;
+///
+/// let mut rainbow = Rainbow::new(Vec::new()).frequency(0.3).spread(1.0);
+/// writeln!(rainbow, "hi").unwrap();
+/// let output = rainbow.finish().unwrap();
+/// assert_eq!(
+///     b"\x1b[38;2;128;238;18mh\x1b[38;2;166;214;4mi\x1b[39m\n".as_slice(),
+///     output
+/// );
+/// ```
+#[derive(Debug)]
+pub struct Rainbow<W: Write> {
+    writer: W,
+    frequency: f32,
+    spread: f32,
+    seed: f32,
+    offset: f32,
+    encoding: ColorEncoding,
+    line: usize,
+    column: usize,
+    state: State,
+    /// The foreground color is set and must be reset at the end of the line.
+    active: bool,
+    /// The last color written, if no escape sequence of the input was written after it.
+    color: Option<Color>,
+    partial: ([u8; 4], usize),
+    scratch: Vec<u8>,
+    colors: ColorAdjust,
+}
+
+impl<W: Write> Rainbow<W> {
+    /// Initializes a Rainbow over writer, with the frequency 0.1 and spread 3 of lolcat.
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            frequency: 0.1,
+            spread: 3.0,
+            seed: 0.0,
+            offset: 0.0,
+            encoding: ColorEncoding::default(),
+            line: 0,
+            column: 0,
+            state: State::Text,
+            active: false,
+            color: None,
+            partial: ([0; 4], 0),
+            scratch: Vec::new(),
+            colors: ColorAdjust::default(),
+        }
+    }
+
+    /// Set how fast the hue changes, in radians per step.
+    pub fn frequency(mut self, frequency: f32) -> Self {
+        self.frequency = frequency;
+        self
+    }
+
+    /// Set how many characters of a line take a step. Greater values give wider stripes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if spread is not positive.
+    pub fn spread(mut self, spread: f32) -> Self {
+        assert!(spread > 0.0, "the rainbow spread must be positive");
+        self.spread = spread;
+        self
+    }
+
+    /// Set the step of the first character, to start at another hue.
+    pub fn seed(mut self, seed: f32) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Set the colors the terminal can show. The rainbow uses the nearest colors of depth.
+    pub fn color_depth(mut self, depth: ColorDepth) -> Self {
+        self.colors.depth = depth;
+        self
+    }
+
+    /// Write the colors as they look with a color vision deficiency, like
+    /// `AnsiEscapeStream::set_cvd_simulation`, or `None` to write them as they are.
+    pub fn cvd_simulation(mut self, deficiency: Option<Deficiency>) -> Self {
+        self.colors.simulation = deficiency;
+        self
+    }
+
+    /// Set the encoding used to write rich colors.
+    pub fn color_encoding(mut self, encoding: ColorEncoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    /// Returns the animation offset.
+    pub fn offset(&self) -> f32 {
+        self.offset
+    }
+
+    /// Shift the colors by offset steps. Increase it between frames which write the same text,
+    /// to make the rainbow move.
+    pub fn set_offset(&mut self, offset: f32) {
+        self.offset = offset;
+    }
+
+    /// Start again at the first line, like a new adapter. Use it when a frame is redrawn.
+    pub fn rewind(&mut self) {
+        self.line = 0;
+        self.column = 0;
+    }
+
+    /// Returns a reference to the writer.
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    /// Returns a mutable reference to the writer. Writing to it directly may mix with a color
+    /// which is not reset yet.
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.writer
+    }
+
+    /// Reset the foreground color if a line is not terminated, flush, and return the writer.
+    /// Bytes of an incomplete character are written as they are.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.scratch.clear();
+        let (bytes, len) = self.partial;
+        self.scratch.extend_from_slice(&bytes[..len]);
+        self.reset_color()?;
+        self.writer.write_all(&self.scratch)?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+
+    /// Returns the color of the current character.
+    fn color_at(&mut self) -> Color {
+        let step = self.seed + self.offset + self.line as f32 + self.column as f32 / self.spread;
+        let angle = self.frequency * step;
+        let wave = |phase: f32| ((angle + phase).sin() * 127.0 + 128.0).round() as u8;
+        let rgb = (wave(0.0), wave(2.0 * PI / 3.0), wave(4.0 * PI / 3.0));
+        self.colors.downgrade(rgb)
+    }
+
+    fn reset_color(&mut self) -> io::Result<()> {
+        if self.active {
+            encode::write_attribute(&mut IoSink(&mut self.scratch), FC_DEFAULT)?;
+            self.active = false;
+            self.color = None;
+        }
+        Ok(())
+    }
+
+    fn push_char(&mut self, c: char) -> io::Result<()> {
+        let mut bytes = [0; 4];
+        let bytes = c.encode_utf8(&mut bytes).as_bytes();
+        match self.state {
+            State::Text => match c {
+                '\n' => {
+                    self.reset_color()?;
+                    self.line += 1;
+                    self.column = 0;
+                }
+                '\x1b' => self.state = State::Escape,
+                c if c.is_control() || extends(c) => {}
+                c if c.is_whitespace() => self.column += 1,
+                _ => {
+                    let color = self.color_at();
+                    if self.color != Some(color) {
+                        let style = self.colors.style(&Style::new().fg(color));
+                        encode::write_style(&mut IoSink(&mut self.scratch), &style, self.encoding)?;
+                        self.color = Some(color);
+                        self.active = true;
+                    }
+                    self.column += 1;
+                }
+            },
+            State::Escape => {
+                self.state = match c {
+                    '[' => State::Csi,
+                    ']' => State::Osc,
+                    _ => State::Text,
+                }
+            }
+            State::Csi if ('\x40'..='\x7e').contains(&c) => self.state = State::Text,
+            State::Osc if c == '\x07' => self.state = State::Text,
+            State::Osc if c == '\x1b' => self.state = State::Escape,
+            State::Csi | State::Osc => {}
+        }
+        if self.state != State::Text {
+            // the input may change the colors
+            self.color = None;
+        }
+        self.scratch.extend_from_slice(bytes);
+        Ok(())
+    }
+
+    fn push_str(&mut self, text: &str) -> io::Result<()> {
+        text.chars().try_for_each(|c| self.push_char(c))
+    }
+
+    /// Color input, keeping an incomplete character at its end for the next write.
+    fn push(&mut self, mut input: &[u8]) -> io::Result<()> {
+        let (mut bytes, len) = self.partial;
+        if len > 0 {
+            let width = match bytes[0] {
+                0xf0.. => 4,
+                0xe0.. => 3,
+                _ => 2,
+            };
+            // only continuation bytes complete the character
+            let taken = input
+                .iter()
+                .take(width - len)
+                .take_while(|&&b| (0x80..=0xbf).contains(&b))
+                .count();
+            bytes[len..len + taken].copy_from_slice(&input[..taken]);
+            input = &input[taken..];
+            self.partial.1 = 0;
+            if len + taken < width {
+                if input.is_empty() {
+                    self.partial = (bytes, len + taken);
+                    return Ok(());
+                }
+                self.scratch.extend_from_slice(&bytes[..len + taken]);
+            } else {
+                match str::from_utf8(&bytes[..width]) {
+                    Ok(text) => self.push_str(text)?,
+                    Err(_) => self.scratch.extend_from_slice(&bytes[..width]),
+                }
+            }
+        }
+        loop {
+            match str::from_utf8(input) {
+                Ok(text) => return self.push_str(text),
+                Err(error) => {
+                    let (valid, rest) = input.split_at(error.valid_up_to());
+                    // valid was checked by from_utf8
+                    self.push_str(str::from_utf8(valid).unwrap_or_default())?;
+                    match error.error_len() {
+                        Some(len) => {
+                            self.scratch.extend_from_slice(&rest[..len]);
+                            input = &rest[len..];
+                        }
+                        None => {
+                            self.partial.0[..rest.len()].copy_from_slice(rest);
+                            self.partial.1 = rest.len();
+                            return Ok(());
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl<W: Write> Write for Rainbow<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.scratch.clear();
+        self.push(buf)?;
+        self.writer.write_all(&self.scratch)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn colorize(rainbow: Rainbow<Vec<u8>>, chunks: &[&[u8]]) -> String {
+        let mut rainbow = rainbow;
+        for chunk in chunks {
+            rainbow.write_all(chunk).unwrap();
+        }
+        String::from_utf8_lossy(&rainbow.finish().unwrap()).into_owned()
+    }
+
+    #[test]
+    fn test_lines() {
+        let rainbow = Rainbow::new(Vec::new())
+            .frequency(PI / 2.0)
+            .spread(1.0)
+            .color_depth(ColorDepth::Ansi16);
+        // the hue changes on each character, and each line starts one step further
+        assert_eq!(
+            "\x1b[92ma\x1b[91mb\x1b[39m\n\x1b[91mc \x1b[36md\x1b[39m",
+            colorize(rainbow, &[b"ab\nc", b" d"])
+        );
+        let rainbow = Rainbow::new(Vec::new())
+            .frequency(PI / 2.0)
+            .spread(1.0)
+            .color_depth(ColorDepth::Ansi16);
+        // an incomplete character is written as it is when the next byte can not complete it
+        assert_eq!(
+            "\x1b[92ma\u{fffd}\x1b[39m\n\x1b[91mb\x1b[39m",
+            colorize(rainbow, &[b"a\xc3", b"\nb"])
+        );
+    }
+
+    #[test]
+    fn test_spread_and_offset() {
+        let mut rainbow = Rainbow::new(Vec::new())
+            .frequency(PI / 2.0)
+            .spread(2.0)
+            .color_depth(ColorDepth::Ansi16);
+        rainbow.set_offset(1.0);
+        assert_eq!(1.0, rainbow.offset());
+        assert_eq!(
+            "\x1b[91ma\x1b[35mb\x1b[94mcd\x1b[39m",
+            colorize(rainbow, &[b"abcd"])
+        );
+    }
+
+    #[test]
+    fn test_cvd_simulation() {
+        let rainbow = Rainbow::new(Vec::new())
+            .frequency(PI / 2.0)
+            .spread(1.0)
+            .color_depth(ColorDepth::Ansi16);
+        let mut colors = ColorAdjust {
+            depth: ColorDepth::Ansi16,
+            simulation: Some(Deficiency::Deuteranopia),
+            ..ColorAdjust::default()
+        };
+        let simulated = colors.style(&Style::new().fg(Color::LightGreen));
+        assert_ne!(Some(Color::LightGreen), simulated.foreground);
+        let mut expected = Vec::new();
+        encode::write_style(
+            &mut IoSink(&mut expected),
+            &simulated,
+            ColorEncoding::Semicolon,
+        )
+        .unwrap();
+        expected.extend_from_slice(b"a\x1b[39m");
+        let rainbow = rainbow.cvd_simulation(Some(Deficiency::Deuteranopia));
+        assert_eq!(
+            String::from_utf8(expected).unwrap(),
+            colorize(rainbow, &[b"a"])
+        );
+    }
+
+    #[test]
+    fn test_passthrough() {
+        let rainbow = Rainbow::new(Vec::new()).color_depth(ColorDepth::Ansi256);
+        // escape sequences are not colored, and the color is written again after them
+        assert_eq!(
+            "\x1b[38;5;118ma\x1b[1m\x1b[38;5;118mb\x1b]0;t\x07\x1b[38;5;112mc\x1b[39m",
+            colorize(rainbow, &[b"a\x1b[1mb\x1b]0;t\x07c"])
+        );
+        let rainbow = Rainbow::new(Vec::new()).color_depth(ColorDepth::Ansi256);
+        // a character split between writes, combining marks and invalid bytes
+        let output = colorize(rainbow, &[b"\xc3", b"\xa9e\xcc\x81\xff"]);
+        assert_eq!("\x1b[38;5;118m\u{e9}e\u{301}\u{fffd}\x1b[39m", output);
+    }
+}
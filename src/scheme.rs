@@ -0,0 +1,729 @@
+//! Loaders of terminal color scheme files.
+
+use std::{fmt, ops::Range};
+
+use crate::{Color, Rgb, XTERM_PALETTE};
+
+/// The colors a terminal shows: the 16 named colors, and the default foreground and
+/// background. It is loaded from the scheme files of terminals, to know the RGB values of
+/// named colors, like when rendering output as HTML.
+///
+/// The loaders are lenient: unknown entries are ignored, and colors missing from the file
+/// keep the xterm defaults of `ColorScheme::default()`. A file without any color is an error.
+///
+/// # Examples
+///
+/// ```
+/// use ansistream::{Color, ColorScheme};
+///
+/// let scheme = ColorScheme::from_xresources(
+///     "! tomorrow night\n*.foreground: #c5c8c6\n*.background: #1d1f21\n*.color1: #cc6666\n",
+/// )
+/// .unwrap();
+/// assert_eq!((204, 102, 102), scheme.resolve_fg(Color::Red));
+/// assert_eq!((29, 31, 33), scheme.resolve_bg(Color::Default));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ColorScheme {
+    pub foreground: Rgb,
+    pub background: Rgb,
+    /// The RGB values of the named colors, in the order of `Color::NAMED`.
+    pub palette: [Rgb; 16],
+}
+
+/// Why a scheme file could not be loaded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseSchemeErrorKind {
+    /// A color value which could not be parsed.
+    InvalidColor,
+    /// The structure of the file is broken, like an unterminated element.
+    Syntax,
+    /// The file has no color of the scheme.
+    NoColors,
+}
+
+/// A scheme file which could not be loaded, with the line of the problem.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseSchemeError {
+    kind: ParseSchemeErrorKind,
+    line: usize,
+}
+
+impl ParseSchemeError {
+    /// Returns why the file could not be loaded.
+    pub fn kind(&self) -> ParseSchemeErrorKind {
+        self.kind
+    }
+
+    /// Returns the line of the problem, starting at 1, or 0 if the problem is not on a line.
+    pub fn line(&self) -> usize {
+        self.line
+    }
+}
+
+impl fmt::Display for ParseSchemeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            ParseSchemeErrorKind::InvalidColor => write!(f, "invalid color at line {}", self.line),
+            ParseSchemeErrorKind::Syntax => write!(f, "syntax error at line {}", self.line),
+            ParseSchemeErrorKind::NoColors => f.write_str("no colors found"),
+        }
+    }
+}
+
+impl std::error::Error for ParseSchemeError {}
+
+/// An entry of a scheme.
+#[derive(Clone, Copy)]
+enum Slot {
+    Foreground,
+    Background,
+    Palette(usize),
+}
+
+/// Names of the named colors in Windows Terminal and Alacritty schemes, in palette order.
+const NAMES: [&str; 8] = [
+    "black", "red", "green", "yellow", "blue", "magenta", "cyan", "white",
+];
+
+/// Returns the line of a byte offset in text, starting at 1.
+fn line_at(text: &str, offset: usize) -> usize {
+    text[..offset].matches('\n').count() + 1
+}
+
+/// Returns the range of the first object of the `schemes` array of a Windows Terminal
+/// settings file, or the whole text if it has no `schemes` member.
+fn first_scheme(text: &str) -> Result<Range<usize>, ParseSchemeError> {
+    let Some(key_end) = text
+        .match_indices("\"schemes\"")
+        .map(|(i, key)| i + key.len())
+        .find(|&i| text[i..].trim_start().starts_with(':'))
+    else {
+        return Ok(0..text.len());
+    };
+    let syntax = |offset| ParseSchemeError {
+        kind: ParseSchemeErrorKind::Syntax,
+        line: line_at(text, offset),
+    };
+    let value = text[key_end..].trim_start()[1..].trim_start();
+    let Some(array) = value.strip_prefix('[') else {
+        return Err(syntax(text.len() - value.len()));
+    };
+    let open = text.len() - array.trim_start().len();
+    if text[open..].starts_with(']') {
+        return Ok(open..open);
+    }
+    if !text[open..].starts_with('{') {
+        return Err(syntax(open));
+    }
+    let (mut depth, mut string, mut escaped) = (0, false, false);
+    for (i, c) in text[open..].char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if string => escaped = true,
+            '"' => string = !string,
+            _ if string => {}
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Ok(open..open + i + 1);
+                }
+            }
+            _ => {}
+        }
+    }
+    Err(syntax(open))
+}
+
+/// Parse a hex color like `#1d1f21`, `1d1f21`, `0x1d1f21` or `#ccc`.
+fn parse_hex(value: &str) -> Option<Rgb> {
+    let digits = value
+        .strip_prefix('#')
+        .or_else(|| value.strip_prefix("0x"))
+        .or_else(|| value.strip_prefix("0X"))
+        .unwrap_or(value);
+    match Color::from_hex(digits)? {
+        Color::Rgb(r, g, b) => Some((r, g, b)),
+        _ => None,
+    }
+}
+
+/// Parse a X11 color, in hex or in the `rgb:rr/gg/bb` form with 1 to 4 digits by component.
+fn parse_x11(value: &str) -> Option<Rgb> {
+    let Some(components) = value.strip_prefix("rgb:") else {
+        return parse_hex(value);
+    };
+    let mut parts = components.split('/').map(|part| {
+        let max = 16u32.checked_pow(part.len() as u32)?.checked_sub(1)?;
+        let value = u32::from_str_radix(part, 16).ok()?;
+        (1..=4)
+            .contains(&part.len())
+            .then(|| ((value * 255 + max / 2) / max) as u8)
+    });
+    let rgb = (parts.next()??, parts.next()??, parts.next()??);
+    parts.next().is_none().then_some(rgb)
+}
+
+impl ColorScheme {
+    /// Returns the RGB value of a foreground color. Named colors and the first 16 colors of
+    /// the 256 color palette use the scheme, `Default` is the foreground of the scheme.
+    pub fn resolve_fg(&self, color: Color) -> Rgb {
+        self.resolve(color).unwrap_or(self.foreground)
+    }
+
+    /// Returns the RGB value of a background color. `Default` is the background of the scheme.
+    pub fn resolve_bg(&self, color: Color) -> Rgb {
+        self.resolve(color).unwrap_or(self.background)
+    }
+
+    fn resolve(&self, color: Color) -> Option<Rgb> {
+        match color {
+            Color::Default => None,
+            Color::Rgb(r, g, b) => Some((r, g, b)),
+            Color::Ansi256(index) => Some(match self.palette.get(usize::from(index)) {
+                Some(&rgb) => rgb,
+                None => XTERM_PALETTE[usize::from(index)],
+            }),
+            named => Color::NAMED
+                .iter()
+                .position(|&c| c == named)
+                .map(|index| self.palette[index]),
+        }
+    }
+
+    fn set(&mut self, slot: Slot, rgb: Rgb) {
+        match slot {
+            Slot::Foreground => self.foreground = rgb,
+            Slot::Background => self.background = rgb,
+            Slot::Palette(index) => self.palette[index] = rgb,
+        }
+    }
+
+    /// Load the X resources of a terminal, like `*.color1: #cc6666` or `URxvt.foreground`.
+    /// Colors can be in hex or `rgb:` form, or names of `#define` lines.
+    pub fn from_xresources(text: &str) -> Result<Self, ParseSchemeError> {
+        let mut scheme = ColorScheme::default();
+        let mut found = false;
+        let mut defines = Vec::new();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if let Some(define) = line.strip_prefix("#define") {
+                let mut words = define.split_whitespace();
+                if let (Some(name), Some(value)) = (words.next(), words.next()) {
+                    defines.push((name, value));
+                }
+                continue;
+            }
+            if line.starts_with('!') || line.starts_with('#') {
+                continue;
+            }
+            let Some((resource, value)) = line.split_once(':') else {
+                continue;
+            };
+            let name = resource
+                .rsplit(['.', '*'])
+                .next()
+                .unwrap_or_default()
+                .trim();
+            let slot = match name {
+                "foreground" => Slot::Foreground,
+                "background" => Slot::Background,
+                _ => match name.strip_prefix("color").and_then(|n| n.parse().ok()) {
+                    Some(index @ 0..=15) => Slot::Palette(index),
+                    _ => continue,
+                },
+            };
+            let value = value.trim();
+            let value = defines
+                .iter()
+                .rev()
+                .find(|(name, _)| *name == value)
+                .map_or(value, |(_, value)| value);
+            let error = ParseSchemeError {
+                kind: ParseSchemeErrorKind::InvalidColor,
+                line: i + 1,
+            };
+            scheme.set(slot, parse_x11(value).ok_or(error)?);
+            found = true;
+        }
+        scheme.found(found)
+    }
+
+    /// Load an iTerm2 `.itermcolors` property list. The components are read as sRGB, whatever
+    /// the color space of the file.
+    pub fn from_itermcolors(text: &str) -> Result<Self, ParseSchemeError> {
+        let syntax = |offset| ParseSchemeError {
+            kind: ParseSchemeErrorKind::Syntax,
+            line: line_at(text, offset),
+        };
+        let mut scheme = ColorScheme::default();
+        let mut found = false;
+        let mut offset = 0;
+        while let Some(start) = text[offset..].find("<key>") {
+            let key_start = offset + start + "<key>".len();
+            let key_end = key_start + text[key_start..].find("</key>").ok_or(syntax(key_start))?;
+            let key = text[key_start..key_end].trim();
+            offset = key_end + "</key>".len();
+            let rest = text[offset..].trim_start();
+            if !rest.starts_with("<dict>") {
+                continue;
+            }
+            let dict_start = text.len() - rest.len();
+            let dict_end = dict_start + rest.find("</dict>").ok_or(syntax(dict_start))?;
+            let dict = &text[dict_start..dict_end];
+            offset = dict_end + "</dict>".len();
+            let slot = match key {
+                "Foreground Color" => Slot::Foreground,
+                "Background Color" => Slot::Background,
+                _ => match key
+                    .strip_prefix("Ansi ")
+                    .and_then(|k| k.strip_suffix(" Color"))
+                    .and_then(|n| n.parse().ok())
+                {
+                    Some(index @ 0..=15) => Slot::Palette(index),
+                    _ => continue,
+                },
+            };
+            let component = |name: &str| -> Result<u8, ParseSchemeError> {
+                let invalid = ParseSchemeError {
+                    kind: ParseSchemeErrorKind::InvalidColor,
+                    line: line_at(text, dict_start),
+                };
+                let key = format!("<key>{name} Component</key>");
+                let value = dict.find(&key).ok_or(invalid)? + key.len();
+                let value = dict[value..].trim_start();
+                let value = value
+                    .strip_prefix("<real>")
+                    .or_else(|| value.strip_prefix("<integer>"))
+                    .ok_or(invalid)?;
+                let value = &value[..value.find('<').ok_or(invalid)?];
+                let value: f32 = value.trim().parse().map_err(|_| invalid)?;
+                Ok((value.clamp(0.0, 1.0) * 255.0).round() as u8)
+            };
+            let rgb = (component("Red")?, component("Green")?, component("Blue")?);
+            scheme.set(slot, rgb);
+            found = true;
+        }
+        scheme.found(found)
+    }
+
+    /// Load a Windows Terminal scheme, the JSON object of an entry of `schemes`, with members
+    /// like `"brightRed": "#E74856"`. For a settings file, only the first entry of `schemes` is
+    /// read, the colors of profiles are ignored.
+    pub fn from_windows_terminal(text: &str) -> Result<Self, ParseSchemeError> {
+        let mut scheme = ColorScheme::default();
+        let mut seen = [false; 18];
+        let range = first_scheme(text)?;
+        let mut offset = range.start;
+        let mut previous: Option<&str> = None;
+        while let Some(start) = text[offset..range.end].find('"') {
+            let start = offset + start + 1;
+            let mut end = start;
+            let mut escaped = false;
+            for (i, c) in text[start..].char_indices() {
+                match c {
+                    '\\' if !escaped => escaped = true,
+                    '"' if !escaped => {
+                        end = start + i;
+                        break;
+                    }
+                    _ => escaped = false,
+                }
+            }
+            if end == start && !text[start..].starts_with('"') {
+                return Err(ParseSchemeError {
+                    kind: ParseSchemeErrorKind::Syntax,
+                    line: line_at(text, start),
+                });
+            }
+            let string = &text[start..end];
+            offset = end + 1;
+            // a string after a key and a colon is its value
+            let key = previous.take();
+            if text[offset..].trim_start().starts_with(':') {
+                previous = Some(string);
+                continue;
+            }
+            let Some(key) = key else {
+                continue;
+            };
+            let (slot, index) = match key {
+                "foreground" => (Slot::Foreground, 16),
+                "background" => (Slot::Background, 17),
+                _ => {
+                    let (name, bright) = match key.strip_prefix("bright") {
+                        Some(name) => (name, 8),
+                        None => (key, 0),
+                    };
+                    // names are lowercase, and capitalized after bright
+                    if (bright == 0) != name.starts_with(char::is_lowercase) {
+                        continue;
+                    }
+                    let name = if name.eq_ignore_ascii_case("purple") {
+                        "magenta"
+                    } else {
+                        name
+                    };
+                    match NAMES.iter().position(|n| n.eq_ignore_ascii_case(name)) {
+                        Some(i) => (Slot::Palette(i + bright), i + bright),
+                        None => continue,
+                    }
+                }
+            };
+            if seen[index] {
+                continue;
+            }
+            let error = ParseSchemeError {
+                kind: ParseSchemeErrorKind::InvalidColor,
+                line: line_at(text, start),
+            };
+            scheme.set(slot, parse_hex(string).ok_or(error)?);
+            seen[index] = true;
+        }
+        scheme.found(seen.contains(&true))
+    }
+
+    /// Load the colors of an Alacritty TOML configuration, the `colors.primary`,
+    /// `colors.normal` and `colors.bright` tables.
+    pub fn from_alacritty(text: &str) -> Result<Self, ParseSchemeError> {
+        let mut scheme = ColorScheme::default();
+        let mut found = false;
+        let mut table = String::new();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some(header) = line.strip_prefix('[') {
+                let header = header.split(']').next().unwrap_or_default();
+                table = header.trim().replace([' ', '"', '\''], "");
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            let key = key.trim().trim_matches(['"', '\'']);
+            let path = if table.is_empty() {
+                key.to_string()
+            } else {
+                format!("{table}.{key}")
+            };
+            let value = value.trim();
+            let pairs: Vec<(String, &str)> = match value.strip_prefix('{') {
+                // an inline table, like normal = { black = "#000000", red = "#cd0000" }
+                Some(inline) => inline
+                    .trim_end()
+                    .trim_end_matches('}')
+                    .split(',')
+                    .filter_map(|pair| pair.split_once('='))
+                    .map(|(k, v)| (format!("{path}.{}", k.trim()), v))
+                    .collect(),
+                None => vec![(path, value)],
+            };
+            for (path, value) in pairs {
+                let slot = match path.strip_prefix("colors.") {
+                    Some("primary.foreground") => Slot::Foreground,
+                    Some("primary.background") => Slot::Background,
+                    Some(path) => {
+                        let (offset, name) = match path.split_once('.') {
+                            Some(("normal", name)) => (0, name),
+                            Some(("bright", name)) => (8, name),
+                            _ => continue,
+                        };
+                        match NAMES.iter().position(|n| *n == name) {
+                            Some(index) => Slot::Palette(offset + index),
+                            None => continue,
+                        }
+                    }
+                    None => continue,
+                };
+                let value = value.trim();
+                let quoted = value
+                    .strip_prefix(['"', '\''])
+                    .and_then(|v| v.split(['"', '\'']).next());
+                let error = ParseSchemeError {
+                    kind: ParseSchemeErrorKind::InvalidColor,
+                    line: i + 1,
+                };
+                scheme.set(slot, quoted.and_then(parse_hex).ok_or(error)?);
+                found = true;
+            }
+        }
+        scheme.found(found)
+    }
+
+    /// Load a base16 scheme, with the `base00` to `base0F` colors in YAML. They are mapped to
+    /// the terminal colors like base16-shell does: base00 is the background and black, base05
+    /// the foreground and white, base08 red and so on.
+    pub fn from_base16(text: &str) -> Result<Self, ParseSchemeError> {
+        // the palette colors of each base color
+        const MAP: [&[usize]; 16] = [
+            &[0],
+            &[],
+            &[],
+            &[8],
+            &[],
+            &[7],
+            &[],
+            &[15],
+            &[1, 9],
+            &[],
+            &[3, 11],
+            &[2, 10],
+            &[6, 14],
+            &[4, 12],
+            &[5, 13],
+            &[],
+        ];
+        let mut scheme = ColorScheme::default();
+        let mut found = false;
+        for (i, line) in text.lines().enumerate() {
+            let Some((key, value)) = line.trim().split_once(':') else {
+                continue;
+            };
+            let Some(base) = key
+                .trim()
+                .trim_matches(['"', '\''])
+                .strip_prefix("base0")
+                .filter(|n| n.len() == 1)
+                .and_then(|n| usize::from_str_radix(n, 16).ok())
+            else {
+                continue;
+            };
+            let value = value.trim();
+            let value = match value.strip_prefix(['"', '\'']) {
+                Some(quoted) => quoted.split(['"', '\'']).next().unwrap_or_default(),
+                None => value.split(" #").next().unwrap_or_default().trim(),
+            };
+            let error = ParseSchemeError {
+                kind: ParseSchemeErrorKind::InvalidColor,
+                line: i + 1,
+            };
+            let rgb = parse_hex(value).ok_or(error)?;
+            for &index in MAP[base] {
+                scheme.palette[index] = rgb;
+            }
+            match base {
+                0 => scheme.background = rgb,
+                5 => scheme.foreground = rgb,
+                _ => {}
+            }
+            found = true;
+        }
+        scheme.found(found)
+    }
+
+    fn found(self, found: bool) -> Result<Self, ParseSchemeError> {
+        if found {
+            Ok(self)
+        } else {
+            Err(ParseSchemeError {
+                kind: ParseSchemeErrorKind::NoColors,
+                line: 0,
+            })
+        }
+    }
+}
+
+impl Default for ColorScheme {
+    /// The xterm defaults, light gray on black.
+    fn default() -> Self {
+        let mut palette = [(0, 0, 0); 16];
+        palette.copy_from_slice(&XTERM_PALETTE[..16]);
+        ColorScheme {
+            foreground: (229, 229, 229),
+            background: (0, 0, 0),
+            palette,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_xresources() {
+        let text = "\
+! comment
+#define base08 #cc6666
+URxvt*foreground: rgb:c5/c8/c6
+*.background:\t#1d1f21
+*color1: base08
+XTerm.color9: rgb:fff/0/8000
+*.cursorColor: #ffffff
+*.color16: #000000
+";
+        let scheme = ColorScheme::from_xresources(text).unwrap();
+        assert_eq!((197, 200, 198), scheme.foreground);
+        assert_eq!((29, 31, 33), scheme.background);
+        assert_eq!((204, 102, 102), scheme.palette[1]);
+        assert_eq!((255, 0, 128), scheme.palette[9]);
+        // missing colors keep the defaults
+        assert_eq!(XTERM_PALETTE[2], scheme.palette[2]);
+
+        let error = ColorScheme::from_xresources("*.color0: #000\n*.color1: red").unwrap_err();
+        assert_eq!(ParseSchemeErrorKind::InvalidColor, error.kind());
+        assert_eq!(2, error.line());
+        let error = ColorScheme::from_xresources("! nothing").unwrap_err();
+        assert_eq!(ParseSchemeErrorKind::NoColors, error.kind());
+    }
+
+    #[test]
+    fn test_itermcolors() {
+        let text = r#"<?xml version="1.0" encoding="UTF-8"?>
+<plist version="1.0">
+<dict>
+	<key>Ansi 1 Color</key>
+	<dict>
+		<key>Alpha Component</key>
+		<real>1</real>
+		<key>Blue Component</key>
+		<real>0.4</real>
+		<key>Color Space</key>
+		<string>sRGB</string>
+		<key>Green Component</key>
+		<real>0.4</real>
+		<key>Red Component</key>
+		<real>0.8</real>
+	</dict>
+	<key>Background Color</key>
+	<dict>
+		<key>Blue Component</key>
+		<integer>0</integer>
+		<key>Green Component</key>
+		<real>0</real>
+		<key>Red Component</key>
+		<real>1</real>
+	</dict>
+	<key>Selection Color</key>
+	<dict>
+		<key>Blue Component</key>
+		<real>1</real>
+	</dict>
+</dict>
+</plist>
+"#;
+        let scheme = ColorScheme::from_itermcolors(text).unwrap();
+        assert_eq!((204, 102, 102), scheme.palette[1]);
+        assert_eq!((255, 0, 0), scheme.background);
+
+        let broken = "<dict>\n<key>Ansi 0 Color</key>\n<dict><key>Red Component</key>";
+        let error = ColorScheme::from_itermcolors(broken).unwrap_err();
+        assert_eq!(ParseSchemeErrorKind::Syntax, error.kind());
+        assert_eq!(3, error.line());
+    }
+
+    #[test]
+    fn test_windows_terminal() {
+        let text = r##"{
+    "name": "Campbell \"dark\"",
+    "foreground": "#CCCCCC",
+    "background": "#0C0C0C",
+    "red": "#C50F1F",
+    "purple": "#881798",
+    "brightBlack": "#767676",
+    "brightPurple": "#B4009E",
+    "Red": "#000000",
+    "brightWhite": "#F2F2F2"
+}"##;
+        let scheme = ColorScheme::from_windows_terminal(text).unwrap();
+        assert_eq!((204, 204, 204), scheme.foreground);
+        assert_eq!((12, 12, 12), scheme.background);
+        assert_eq!((197, 15, 31), scheme.palette[1]);
+        assert_eq!((136, 23, 152), scheme.palette[5]);
+        assert_eq!((118, 118, 118), scheme.palette[8]);
+        assert_eq!((180, 0, 158), scheme.palette[13]);
+        assert_eq!((242, 242, 242), scheme.palette[15]);
+
+        // the first scheme of a settings file
+        let text = r##"{"schemes": [{"red": "#111111"}, {"red": "#222222"}]}"##;
+        let scheme = ColorScheme::from_windows_terminal(text).unwrap();
+        assert_eq!((17, 17, 17), scheme.palette[1]);
+        // colors outside of the scheme, like the background of a profile, are ignored
+        let text = r##"{
+    "profiles": {"defaults": {"background": "#012456", "name": "{\"}"}},
+    "schemes": [{"name": "Campbell", "background": "#0C0C0C"}],
+    "foreground": "#FFFFFF"
+}"##;
+        let scheme = ColorScheme::from_windows_terminal(text).unwrap();
+        assert_eq!((12, 12, 12), scheme.background);
+        assert_eq!(ColorScheme::default().foreground, scheme.foreground);
+        let error = ColorScheme::from_windows_terminal(r#"{"schemes": []}"#).unwrap_err();
+        assert_eq!(ParseSchemeErrorKind::NoColors, error.kind());
+        let error = ColorScheme::from_windows_terminal("{\n\"schemes\": {}}").unwrap_err();
+        assert_eq!(ParseSchemeErrorKind::Syntax, error.kind());
+        assert_eq!(2, error.line());
+        let error = ColorScheme::from_windows_terminal("{\n\"red\": \"blue\"}").unwrap_err();
+        assert_eq!(ParseSchemeErrorKind::InvalidColor, error.kind());
+        assert_eq!(2, error.line());
+    }
+
+    #[test]
+    fn test_alacritty() {
+        let text = r##"
+[colors.primary]
+background = '#1d1f21' # dark
+foreground = "0xc5c8c6"
+
+[colors.normal]
+red = "#cc6666"
+
+[colors.bright]
+black = "#969896"
+
+[window]
+opacity = 0.9
+"##;
+        let scheme = ColorScheme::from_alacritty(text).unwrap();
+        assert_eq!((29, 31, 33), scheme.background);
+        assert_eq!((197, 200, 198), scheme.foreground);
+        assert_eq!((204, 102, 102), scheme.palette[1]);
+        assert_eq!((150, 152, 150), scheme.palette[8]);
+
+        let text = "colors.normal = { green = '#00ff00', blue = '#0000ff' }";
+        let scheme = ColorScheme::from_alacritty(text).unwrap();
+        assert_eq!((0, 255, 0), scheme.palette[2]);
+        assert_eq!((0, 0, 255), scheme.palette[4]);
+        let error = ColorScheme::from_alacritty("[colors.normal]\nred = 5").unwrap_err();
+        assert_eq!(2, error.line());
+    }
+
+    #[test]
+    fn test_base16() {
+        let text = r##"scheme: "Tomorrow Night"
+author: "Chris Kempson"
+base00: "1d1f21"
+base05: "c5c8c6" # foreground
+base08: cc6666
+palette:
+  base0D: "#81a2be"
+"##;
+        let scheme = ColorScheme::from_base16(text).unwrap();
+        assert_eq!((29, 31, 33), scheme.background);
+        assert_eq!((29, 31, 33), scheme.palette[0]);
+        assert_eq!((197, 200, 198), scheme.foreground);
+        assert_eq!((197, 200, 198), scheme.palette[7]);
+        assert_eq!((204, 102, 102), scheme.palette[1]);
+        assert_eq!((204, 102, 102), scheme.palette[9]);
+        assert_eq!((129, 162, 190), scheme.palette[12]);
+        // keys which are not base00 to base0F are ignored
+        let text = "base00: \"1d1f21\"\nbase010: \"ffffff\"\nbase0FF: \"ffffff\"\n";
+        let scheme = ColorScheme::from_base16(text).unwrap();
+        assert_eq!((29, 31, 33), scheme.background);
+        assert_eq!(ColorScheme::default().palette[1..], scheme.palette[1..]);
+    }
+
+    #[test]
+    fn test_resolve() {
+        let mut scheme = ColorScheme::default();
+        scheme.palette[4] = (1, 2, 3);
+        assert_eq!((1, 2, 3), scheme.resolve_fg(Color::Blue));
+        assert_eq!((1, 2, 3), scheme.resolve_bg(Color::Ansi256(4)));
+        assert_eq!(XTERM_PALETTE[100], scheme.resolve_fg(Color::Ansi256(100)));
+        assert_eq!((9, 9, 9), scheme.resolve_fg(Color::Rgb(9, 9, 9)));
+        assert_eq!((229, 229, 229), scheme.resolve_fg(Color::Default));
+        assert_eq!((0, 0, 0), scheme.resolve_bg(Color::Default));
+    }
+}
@@ -0,0 +1,161 @@
+//! Escape codes built at compile time.
+
+/// Builds the SGR escape code of a fixed style as a `&'static str` literal, so it costs
+/// nothing at runtime and can be used in `const` items and `concat!`.
+///
+/// Parameters are separated by commas:
+///
+/// * attributes: `bold`, `dim`, `italic`, `underline`, `blink`, `invert`, `hidden`, `strike`,
+///   `double_underline`, `framed`, `encircled`, `overlined`, `superscript`, `subscript` and
+///   `reset`.
+/// * `fg = color` and `bg = color`, where color is a named color like `red` or `light_blue`,
+///   `default`, an index of the 256 color palette like `208`, or a RGB color like `(255, 128, 0)`.
+///
+/// Without parameters, the reset code ESC[0m is returned. The values of rich colors are not
+/// checked, use values from 0 to 255.
+///
+/// # Examples
+///
+/// ```
+/// use ansistream::sgr;
+///
+/// const ERR: &str = sgr!(bold, fg = red);
+/// const WARN: &[u8] = sgr!(fg = 208, bg = (40, 40, 40)).as_bytes();
+/// const BANNER: &str = concat!(sgr!(invert), " ready ", sgr!());
+///
+/// assert_eq!("\x1b[1;31m", ERR);
+/// assert_eq!(b"\x1b[38;5;208;48;2;40;40;40m", WARN);
+/// assert_eq!("\x1b[7m ready \x1b[0m", BANNER);
+/// ```
+#[macro_export]
+macro_rules! sgr {
+    () => {
+        "\x1b[0m"
+    };
+    ($($params:tt)+) => {
+        $crate::__sgr!(@split [] [] $($params)+)
+    };
+}
+
+/// Implementation of `sgr!`. It splits the parameters on commas, then maps each one to a
+/// literal and joins them with `concat!`.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __sgr {
+    (@split [$($done:tt)*] [$($param:tt)*] , $($rest:tt)*) => {
+        $crate::__sgr!(@split [$($done)* [$($param)*]] [] $($rest)*)
+    };
+    (@split [$($done:tt)*] [$($param:tt)*] $next:tt $($rest:tt)*) => {
+        $crate::__sgr!(@split [$($done)*] [$($param)* $next] $($rest)*)
+    };
+    // trailing comma
+    (@split [$($done:tt)*] []) => {
+        $crate::__sgr!(@join $($done)*)
+    };
+    (@split [$($done:tt)*] [$($param:tt)*]) => {
+        $crate::__sgr!(@join $($done)* [$($param)*])
+    };
+    (@join [$($first:tt)*] $([$($param:tt)*])*) => {
+        concat!(
+            "\x1b[",
+            $crate::__sgr!(@param $($first)*),
+            $(";", $crate::__sgr!(@param $($param)*),)*
+            "m"
+        )
+    };
+
+    (@param reset) => { "0" };
+    (@param bold) => { "1" };
+    (@param dim) => { "2" };
+    (@param italic) => { "3" };
+    (@param underline) => { "4" };
+    (@param blink) => { "5" };
+    (@param invert) => { "7" };
+    (@param hidden) => { "8" };
+    (@param strike) => { "9" };
+    (@param double_underline) => { "21" };
+    (@param framed) => { "51" };
+    (@param encircled) => { "52" };
+    (@param overlined) => { "53" };
+    (@param superscript) => { "73" };
+    (@param subscript) => { "74" };
+    (@param fg = $color:ident) => { $crate::__sgr!(@named "3" "9" $color) };
+    (@param bg = $color:ident) => { $crate::__sgr!(@named "4" "10" $color) };
+    (@param fg = $n:literal) => { concat!("38;5;", $n) };
+    (@param bg = $n:literal) => { concat!("48;5;", $n) };
+    (@param fg = ($r:literal, $g:literal, $b:literal)) => {
+        concat!("38;2;", $r, ";", $g, ";", $b)
+    };
+    (@param bg = ($r:literal, $g:literal, $b:literal)) => {
+        concat!("48;2;", $r, ";", $g, ";", $b)
+    };
+    (@param $($unknown:tt)*) => {
+        compile_error!(concat!("unknown sgr parameter: ", stringify!($($unknown)*)))
+    };
+
+    // named colors, with the prefix of the normal and of the bright colors
+    (@named $n:literal $b:literal black) => { concat!($n, "0") };
+    (@named $n:literal $b:literal red) => { concat!($n, "1") };
+    (@named $n:literal $b:literal green) => { concat!($n, "2") };
+    (@named $n:literal $b:literal brown) => { concat!($n, "3") };
+    (@named $n:literal $b:literal blue) => { concat!($n, "4") };
+    (@named $n:literal $b:literal magenta) => { concat!($n, "5") };
+    (@named $n:literal $b:literal cyan) => { concat!($n, "6") };
+    (@named $n:literal $b:literal light_gray) => { concat!($n, "7") };
+    (@named $n:literal $b:literal dark_gray) => { concat!($b, "0") };
+    (@named $n:literal $b:literal light_red) => { concat!($b, "1") };
+    (@named $n:literal $b:literal light_green) => { concat!($b, "2") };
+    (@named $n:literal $b:literal yellow) => { concat!($b, "3") };
+    (@named $n:literal $b:literal light_blue) => { concat!($b, "4") };
+    (@named $n:literal $b:literal light_magenta) => { concat!($b, "5") };
+    (@named $n:literal $b:literal light_cyan) => { concat!($b, "6") };
+    (@named $n:literal $b:literal white) => { concat!($b, "7") };
+    (@named $n:literal $b:literal default) => { concat!($n, "9") };
+    (@named $n:literal $b:literal $unknown:ident) => {
+        compile_error!(concat!("unknown color: ", stringify!($unknown)))
+    };
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use crate::{AnsiWrite, Color, Style};
+
+    fn style_code(style: &Style) -> String {
+        let mut buffer = Vec::new();
+        buffer.write_style(style).unwrap();
+        String::from_utf8(buffer).unwrap()
+    }
+
+    #[test]
+    fn test_matches_runtime_style() {
+        assert_eq!(
+            style_code(&Style::new().bold().fg(Color::Red)),
+            sgr!(bold, fg = red)
+        );
+        assert_eq!(
+            style_code(&Style::new().italic().strike().bg(Color::LightCyan)),
+            sgr!(italic, strike, bg = light_cyan,)
+        );
+        assert_eq!(
+            style_code(&Style::new().fg(Color::Ansi256(208)).bg(Color::Rgb(1, 2, 3))),
+            sgr!(fg = 208, bg = (1, 2, 3))
+        );
+        for (color, code) in [
+            (Color::Black, sgr!(fg = black)),
+            (Color::LightGray, sgr!(fg = light_gray)),
+            (Color::DarkGray, sgr!(fg = dark_gray)),
+            (Color::White, sgr!(fg = white)),
+            (Color::Default, sgr!(fg = default)),
+        ] {
+            assert_eq!(style_code(&Style::new().fg(color)), code);
+        }
+    }
+
+    #[test]
+    fn test_const_contexts() {
+        const RESET: &str = sgr!();
+        const LINE: &str = concat!(sgr!(underline, fg = yellow), "x", sgr!(reset));
+        assert_eq!("\x1b[0m", RESET);
+        assert_eq!("\x1b[4;93mx\x1b[0m", LINE);
+    }
+}
@@ -0,0 +1,215 @@
+//! Fixed capacity buffer stored inline, which never allocates.
+
+use core::fmt;
+#[cfg(feature = "std")]
+use std::io;
+
+/// What a `StackBuffer` does when a write does not fit in the remaining capacity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Overflow {
+    /// Flush the buffered data to the writer and keep buffering. Writes larger than the
+    /// capacity go straight to the writer.
+    #[default]
+    Flush,
+    /// Reject the write. Nothing of the rejected write is buffered, and as every escape code
+    /// is a single write, escape codes are never split.
+    Error,
+}
+
+/// A buffer of `N` bytes stored inline, in front of a writer. Together with `AnsiWrite` or
+/// `AnsiFmtWrite` it writes escape codes without any heap allocation.
+///
+/// It implements `fmt::Write` when `W` is a `fmt::Write`, and `io::Write` when `W` is an
+/// `io::Write`. Buffered data is not flushed on drop, as a flush may fail and `Drop` can not
+/// report it: call `flush` or `flush_fmt` before dropping the buffer, or the data is lost.
+///
+/// It is a plain buffer, not an `AnsiEscapeStream`: it has no color encoding, color depth,
+/// theme or color scheme. Escape codes are written by the `AnsiWrite` and `AnsiFmtWrite`
+/// methods as they are, with `ColorEncoding::Semicolon` for rich colors.
+///
+/// # Examples
+///
+/// ```
+/// use ansistream::{AnsiFmtWrite, StackBuffer, FC_GREEN};
+///
+/// let mut console = String::new();
+/// let mut buffer = StackBuffer::<_, 64>::new(&mut console);
+/// buffer.write_text_fc_fmt(FC_GREEN, format_args!("ok")).unwrap();
+/// buffer.flush_fmt().unwrap();
+/// assert_eq!("\x1b[32mok\x1b[39m", console);
+/// ```
+#[derive(Debug)]
+pub struct StackBuffer<W, const N: usize> {
+    writer: W,
+    buffer: [u8; N],
+    len: usize,
+    overflow: Overflow,
+}
+
+impl<W, const N: usize> StackBuffer<W, N> {
+    /// Initializes a StackBuffer which flushes to writer when full.
+    pub const fn new(writer: W) -> Self {
+        Self::with_overflow(writer, Overflow::Flush)
+    }
+
+    /// Initializes a StackBuffer with the given overflow behavior.
+    pub const fn with_overflow(writer: W, overflow: Overflow) -> Self {
+        Self {
+            writer,
+            buffer: [0; N],
+            len: 0,
+            overflow,
+        }
+    }
+
+    /// Returns the buffered data which was not flushed yet.
+    pub fn buffer(&self) -> &[u8] {
+        &self.buffer[..self.len]
+    }
+
+    /// Returns the capacity of the buffer, which is `N`.
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Discard the buffered data.
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    /// Returns a reference to the writer.
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    /// Returns a mutable reference to the writer. Writing to it directly bypasses the buffered data.
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.writer
+    }
+
+    /// Returns true if data fits in the remaining capacity.
+    fn fits(&self, data: &[u8]) -> bool {
+        data.len() <= N - self.len
+    }
+
+    fn push(&mut self, data: &[u8]) {
+        self.buffer[self.len..self.len + data.len()].copy_from_slice(data);
+        self.len += data.len();
+    }
+}
+
+impl<W: fmt::Write, const N: usize> StackBuffer<W, N> {
+    /// Write the buffered data to the writer. The buffered data must be valid utf-8, which is
+    /// always the case when it was written through `fmt::Write`.
+    pub fn flush_fmt(&mut self) -> fmt::Result {
+        let text = core::str::from_utf8(&self.buffer[..self.len]).map_err(|_| fmt::Error)?;
+        self.writer.write_str(text)?;
+        self.len = 0;
+        Ok(())
+    }
+}
+
+impl<W: fmt::Write, const N: usize> fmt::Write for StackBuffer<W, N> {
+    fn write_str(&mut self, text: &str) -> fmt::Result {
+        if !self.fits(text.as_bytes()) {
+            if self.overflow == Overflow::Error {
+                return Err(fmt::Error);
+            }
+            self.flush_fmt()?;
+            if text.len() >= N {
+                return self.writer.write_str(text);
+            }
+        }
+        self.push(text.as_bytes());
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<W: io::Write, const N: usize> io::Write for StackBuffer<W, N> {
+    /// Buffer data. When it does not fit and the overflow behavior is `Overflow::Error`,
+    /// nothing is written and an error of kind `OutOfMemory` is returned.
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        if !self.fits(data) {
+            if self.overflow == Overflow::Error {
+                return Err(io::Error::new(
+                    io::ErrorKind::OutOfMemory,
+                    "the data does not fit in the stack buffer",
+                ));
+            }
+            io::Write::flush(self)?;
+            if data.len() >= N {
+                return self.writer.write(data);
+            }
+        }
+        self.push(data);
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.write_all(&self.buffer[..self.len])?;
+        self.len = 0;
+        self.writer.flush()
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use std::{fmt::Write as _, io::Write as _};
+
+    use super::*;
+    use crate::{AnsiFmtWrite, AnsiWrite, FC_RED};
+
+    #[test]
+    fn test_fmt_flush_when_full() {
+        let mut sink = String::new();
+        let mut buffer = StackBuffer::<_, 8>::new(&mut sink);
+        buffer.write_str("12345").unwrap();
+        assert_eq!(b"12345", buffer.buffer());
+        // does not fit, the buffered data is flushed first
+        buffer.write_str("6789").unwrap();
+        assert_eq!(b"6789", buffer.buffer());
+        // larger than the capacity, written straight to the sink
+        buffer.write_str("abcdefghij").unwrap();
+        assert!(buffer.buffer().is_empty());
+        buffer.flush_fmt().unwrap();
+        assert_eq!("123456789abcdefghij", sink);
+    }
+
+    #[test]
+    fn test_fmt_overflow_error() {
+        let mut buffer = StackBuffer::<_, 8>::with_overflow(String::new(), Overflow::Error);
+        buffer.write_attribute(FC_RED).unwrap();
+        assert!(buffer.write_attribute(FC_RED).is_err());
+        // the rejected escape code was not split
+        assert_eq!(b"\x1b[31m", buffer.buffer());
+        assert!(buffer.get_ref().is_empty());
+    }
+
+    #[test]
+    fn test_io_backend() {
+        let mut buffer = StackBuffer::<_, 16>::new(Vec::<u8>::new());
+        assert_eq!(16, buffer.capacity());
+        AnsiWrite::write_text_fc256(&mut buffer, 208, "abc").unwrap();
+        AnsiWrite::write_text_fc256(&mut buffer, 208, "abc").unwrap();
+        buffer.flush().unwrap();
+        assert_eq!(
+            b"\x1b[38;5;208mabc\x1b[39m\x1b[38;5;208mabc\x1b[39m",
+            buffer.get_ref().as_slice()
+        );
+    }
+
+    #[test]
+    fn test_io_overflow_error() {
+        let mut buffer = StackBuffer::<_, 4>::with_overflow(Vec::<u8>::new(), Overflow::Error);
+        let err = buffer.write_all(b"12345").unwrap_err();
+        assert_eq!(io::ErrorKind::OutOfMemory, err.kind());
+        assert!(buffer.buffer().is_empty());
+        buffer.write_all(b"1234").unwrap();
+        let err = AnsiWrite::write_attribute(&mut buffer, FC_RED).unwrap_err();
+        assert_eq!(io::ErrorKind::OutOfMemory, err.kind());
+        assert_eq!(b"1234", buffer.buffer());
+        buffer.clear();
+        assert!(buffer.buffer().is_empty());
+    }
+}
@@ -0,0 +1,642 @@
+//! Combination of colors and text attributes written as a single SGR sequence.
+
+use core::{
+    fmt,
+    ops::{BitOr, BitOrAssign},
+    str::FromStr,
+};
+
+use crate::{
+    Color, BC_DEFAULT, BC_RICH_COLORS, FC_DEFAULT, FC_RICH_COLORS, TS_BLINK, TS_BOLD, TS_DIM,
+    TS_DOUBLE_UNDERLINE, TS_ENCIRCLED, TS_FRAMED, TS_HIDDEN, TS_INVERT, TS_ITALIC, TS_NO_BLINK,
+    TS_NO_FRAMED_ENCIRCLED, TS_NO_HIDDEN, TS_NO_INVERT, TS_NO_ITALIC_FRAKTUR, TS_NO_OVERLINE,
+    TS_NO_STRIKE, TS_NO_SUPERSCRIPT_SUBSCRIPT, TS_NO_UNDERLINE, TS_OVERLINE, TS_STRIKE,
+    TS_SUBSCRIPT, TS_SUPERSCRIPT, TS_UNDERLINE,
+};
+
+/// Normal intensity, resets both bold and dim.
+const TS_NORMAL_INTENSITY: u16 = 22;
+
+/// A set of text attributes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Attributes(u16);
+
+impl Attributes {
+    pub const BOLD: Attributes = Attributes(1 << 0);
+    pub const DIM: Attributes = Attributes(1 << 1);
+    pub const ITALIC: Attributes = Attributes(1 << 2);
+    pub const UNDERLINE: Attributes = Attributes(1 << 3);
+    pub const BLINK: Attributes = Attributes(1 << 4);
+    pub const INVERT: Attributes = Attributes(1 << 5);
+    pub const HIDDEN: Attributes = Attributes(1 << 6);
+    pub const STRIKE: Attributes = Attributes(1 << 7);
+    pub const DOUBLE_UNDERLINE: Attributes = Attributes(1 << 8);
+    pub const OVERLINED: Attributes = Attributes(1 << 9);
+    pub const FRAMED: Attributes = Attributes(1 << 10);
+    pub const ENCIRCLED: Attributes = Attributes(1 << 11);
+    pub const SUPERSCRIPT: Attributes = Attributes(1 << 12);
+    pub const SUBSCRIPT: Attributes = Attributes(1 << 13);
+
+    /// Every attribute with its SGR code and the SGR code which resets it.
+    pub(crate) const TABLE: [(Attributes, u16, u16); 14] = [
+        (Attributes::BOLD, TS_BOLD, TS_NORMAL_INTENSITY),
+        (Attributes::DIM, TS_DIM, TS_NORMAL_INTENSITY),
+        (Attributes::ITALIC, TS_ITALIC, TS_NO_ITALIC_FRAKTUR),
+        (Attributes::UNDERLINE, TS_UNDERLINE, TS_NO_UNDERLINE),
+        (Attributes::BLINK, TS_BLINK, TS_NO_BLINK),
+        (Attributes::INVERT, TS_INVERT, TS_NO_INVERT),
+        (Attributes::HIDDEN, TS_HIDDEN, TS_NO_HIDDEN),
+        (Attributes::STRIKE, TS_STRIKE, TS_NO_STRIKE),
+        (
+            Attributes::DOUBLE_UNDERLINE,
+            TS_DOUBLE_UNDERLINE,
+            TS_NO_UNDERLINE,
+        ),
+        (Attributes::OVERLINED, TS_OVERLINE, TS_NO_OVERLINE),
+        (Attributes::FRAMED, TS_FRAMED, TS_NO_FRAMED_ENCIRCLED),
+        (Attributes::ENCIRCLED, TS_ENCIRCLED, TS_NO_FRAMED_ENCIRCLED),
+        (
+            Attributes::SUPERSCRIPT,
+            TS_SUPERSCRIPT,
+            TS_NO_SUPERSCRIPT_SUBSCRIPT,
+        ),
+        (
+            Attributes::SUBSCRIPT,
+            TS_SUBSCRIPT,
+            TS_NO_SUPERSCRIPT_SUBSCRIPT,
+        ),
+    ];
+
+    /// Returns an empty set of attributes.
+    pub const fn empty() -> Self {
+        Attributes(0)
+    }
+
+    /// Returns true if no attribute is set.
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// Returns true if every attribute of other is set.
+    pub const fn contains(self, other: Attributes) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Add the attributes of other to this set.
+    pub fn insert(&mut self, other: Attributes) {
+        self.0 |= other.0;
+    }
+
+    /// Remove the attributes of other from this set.
+    pub fn remove(&mut self, other: Attributes) {
+        self.0 &= !other.0;
+    }
+
+    /// The names of the attributes, in the order of TABLE.
+    pub(crate) const NAMES: [&'static str; 14] = [
+        "bold",
+        "dim",
+        "italic",
+        "underline",
+        "blink",
+        "invert",
+        "hidden",
+        "strike",
+        "double_underline",
+        "overlined",
+        "framed",
+        "encircled",
+        "superscript",
+        "subscript",
+    ];
+
+    /// The names git uses for some attributes, in the order of TABLE.
+    const GIT_NAMES: [(Attributes, &'static str); 2] = [
+        (Attributes::UNDERLINE, "ul"),
+        (Attributes::INVERT, "reverse"),
+    ];
+
+    /// Returns the attribute of a name like `bold` or `double_underline`.
+    pub(crate) fn from_name(name: &str) -> Option<Attributes> {
+        Attributes::NAMES
+            .iter()
+            .position(|n| n.eq_ignore_ascii_case(name))
+            .map(|index| Attributes::TABLE[index].0)
+    }
+
+    /// Returns the attribute of a SGR code, like `Attributes::BOLD` for TS_BOLD.
+    pub fn from_code(code: u16) -> Option<Attributes> {
+        Attributes::TABLE
+            .iter()
+            .find(|(_, set, _)| *set == code)
+            .map(|(attr, _, _)| *attr)
+    }
+}
+
+impl BitOr for Attributes {
+    type Output = Attributes;
+
+    fn bitor(self, rhs: Attributes) -> Attributes {
+        Attributes(self.0 | rhs.0)
+    }
+}
+
+impl BitOrAssign for Attributes {
+    fn bitor_assign(&mut self, rhs: Attributes) {
+        self.0 |= rhs.0;
+    }
+}
+
+/// Foreground color, background color and text attributes applied together.
+///
+/// A style can also be parsed from the git config color syntax, see `Style::from_str`, and
+/// displays in that syntax.
+///
+/// # Examples
+///
+/// ```
+/// # #[cfg(feature = "std")] {
+/// use ansistream::{AnsiWrite, Color, Style};
+///
+/// let style = Style::new().fg(Color::Red).bg(Color::Ansi256(236)).bold();
+/// let mut buffer = Vec::<u8>::new();
+/// buffer.write_text_style(&style, "error").unwrap();
+/// assert_eq!(b"\x1b[1;31;48;5;236merror\x1b[22;39;49m", buffer.as_slice());
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Style {
+    pub foreground: Option<Color>,
+    pub background: Option<Color>,
+    pub attributes: Attributes,
+    /// Attributes explicitly turned off, written with their reset code, like `nobold` in git.
+    pub negated: Attributes,
+}
+
+impl Style {
+    /// Returns a style without colors and attributes.
+    pub const fn new() -> Self {
+        Style {
+            foreground: None,
+            background: None,
+            attributes: Attributes::empty(),
+            negated: Attributes::empty(),
+        }
+    }
+
+    /// Returns true if the style has no colors and no attributes.
+    pub const fn is_empty(&self) -> bool {
+        self.foreground.is_none()
+            && self.background.is_none()
+            && self.attributes.is_empty()
+            && self.negated.is_empty()
+    }
+
+    /// Set the foreground color.
+    pub const fn fg(mut self, color: Color) -> Self {
+        self.foreground = Some(color);
+        self
+    }
+
+    /// Set the background color.
+    pub const fn bg(mut self, color: Color) -> Self {
+        self.background = Some(color);
+        self
+    }
+
+    /// Add text attributes.
+    pub const fn attr(mut self, attributes: Attributes) -> Self {
+        self.attributes = Attributes(self.attributes.0 | attributes.0);
+        self
+    }
+
+    /// Turn attributes off, writing their reset code.
+    pub const fn negate(mut self, attributes: Attributes) -> Self {
+        self.negated = Attributes(self.negated.0 | attributes.0);
+        self
+    }
+
+    /// Add the bold attribute.
+    pub const fn bold(self) -> Self {
+        self.attr(Attributes::BOLD)
+    }
+
+    /// Add the dim attribute.
+    pub const fn dim(self) -> Self {
+        self.attr(Attributes::DIM)
+    }
+
+    /// Add the italic attribute.
+    pub const fn italic(self) -> Self {
+        self.attr(Attributes::ITALIC)
+    }
+
+    /// Add the underline attribute.
+    pub const fn underline(self) -> Self {
+        self.attr(Attributes::UNDERLINE)
+    }
+
+    /// Add the blink attribute.
+    pub const fn blink(self) -> Self {
+        self.attr(Attributes::BLINK)
+    }
+
+    /// Add the invert attribute.
+    pub const fn invert(self) -> Self {
+        self.attr(Attributes::INVERT)
+    }
+
+    /// Add the hidden attribute.
+    pub const fn hidden(self) -> Self {
+        self.attr(Attributes::HIDDEN)
+    }
+
+    /// Add the strike attribute.
+    pub const fn strike(self) -> Self {
+        self.attr(Attributes::STRIKE)
+    }
+
+    /// Parse the parameters of a SGR escape code, like `01;38;5;208` in LS_COLORS or
+    /// GREP_COLORS. A reset code 0 discards the parameters before it, and reset codes of
+    /// attributes, like 22, turn them off.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ansistream::{Color, Style};
+    ///
+    /// let style = Style::from_sgr("01;38;2;255;128;0;44").unwrap();
+    /// assert_eq!(Style::new().bold().fg(Color::Rgb(255, 128, 0)).bg(Color::Blue), style);
+    /// assert_eq!(Style::new(), Style::from_sgr("00").unwrap());
+    /// assert_eq!(7, Style::from_sgr("1;38;5;300").unwrap_err().position());
+    /// ```
+    pub fn from_sgr(params: &str) -> Result<Style, ParseStyleError> {
+        let mut style = Style::new();
+        let mut position = 0;
+        let mut params = params.split(';').map(|param| {
+            let start = position;
+            position += param.len() + 1;
+            let error = ParseStyleError {
+                kind: ParseStyleErrorKind::InvalidSgr,
+                position: start,
+            };
+            match param {
+                "" => Ok((0, error)),
+                param => param
+                    .parse::<u16>()
+                    .map(|code| (code, error))
+                    .map_err(|_| error),
+            }
+        });
+        while let Some(param) = params.next() {
+            let (code, error) = param?;
+            match code {
+                0 => style = Style::new(),
+                30..=37 | FC_DEFAULT | 90..=97 => style.foreground = Color::from_code(code),
+                40..=47 | BC_DEFAULT | 100..=107 => style.background = Color::from_code(code),
+                FC_RICH_COLORS | BC_RICH_COLORS => {
+                    let mut next = || params.next().unwrap_or(Err(error));
+                    let byte = |(value, error): (u16, ParseStyleError)| {
+                        u8::try_from(value).map_err(|_| error)
+                    };
+                    let color = match next()? {
+                        (5, _) => Color::Ansi256(byte(next()?)?),
+                        (2, _) => Color::Rgb(byte(next()?)?, byte(next()?)?, byte(next()?)?),
+                        (_, error) => return Err(error),
+                    };
+                    if code == FC_RICH_COLORS {
+                        style.foreground = Some(color);
+                    } else {
+                        style.background = Some(color);
+                    }
+                }
+                code => match Attributes::from_code(code) {
+                    Some(attr) => {
+                        style.negated.remove(attr);
+                        style = style.attr(attr);
+                    }
+                    None => {
+                        let reset = Attributes::TABLE
+                            .iter()
+                            .filter(|(_, _, reset)| *reset == code)
+                            .fold(Attributes::empty(), |attrs, (attr, _, _)| attrs | *attr);
+                        if reset.is_empty() {
+                            return Err(error);
+                        }
+                        style.attributes.remove(reset);
+                        style = style.negate(reset);
+                    }
+                },
+            }
+        }
+        Ok(style)
+    }
+}
+
+/// Why a style specification could not be parsed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseStyleErrorKind {
+    /// A word which is not an attribute or a color.
+    UnknownWord,
+    /// More than two colors, or more than one with `on`.
+    TooManyColors,
+    /// `on` without a background color after it.
+    MissingBackground,
+    /// A SGR parameter which is not a number, not a known code, or a rich color with missing
+    /// or out of range values.
+    InvalidSgr,
+}
+
+/// A style specification which could not be parsed, with the byte position of the problem.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseStyleError {
+    kind: ParseStyleErrorKind,
+    position: usize,
+}
+
+impl ParseStyleError {
+    /// Returns why the specification could not be parsed.
+    pub fn kind(&self) -> ParseStyleErrorKind {
+        self.kind
+    }
+
+    /// Returns the byte position of the problem in the specification.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Moves the position by offset, when the specification is a part of a larger text.
+    #[cfg(feature = "std")]
+    pub(crate) fn offset(self, offset: usize) -> Self {
+        Self {
+            position: self.position + offset,
+            ..self
+        }
+    }
+}
+
+impl fmt::Display for ParseStyleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = match self.kind {
+            ParseStyleErrorKind::UnknownWord => "unknown attribute or color",
+            ParseStyleErrorKind::TooManyColors => "too many colors",
+            ParseStyleErrorKind::MissingBackground => "expected a background color after on",
+            ParseStyleErrorKind::InvalidSgr => "invalid SGR parameter",
+        };
+        write!(f, "{message} at byte {}", self.position)
+    }
+}
+
+impl core::error::Error for ParseStyleError {}
+
+/// Returns the attribute of a git name like `ul`, or of a name like `underline`.
+fn attribute_from_git_name(name: &str) -> Option<Attributes> {
+    Attributes::GIT_NAMES
+        .iter()
+        .find(|(_, n)| n.eq_ignore_ascii_case(name))
+        .map(|(attr, _)| *attr)
+        .or_else(|| Attributes::from_name(name))
+}
+
+impl FromStr for Style {
+    type Err = ParseStyleError;
+
+    /// Parse the git config color syntax, like `bold red on blue`, `#ff8800 italic` or
+    /// `208 ul nobold`.
+    ///
+    /// Words are separated by spaces, in any order. The first color is the foreground and the
+    /// second one the background, or a color after `on` is the background. `normal` is a color
+    /// which leaves the color unchanged, like in `normal blue`, and `default` is the default
+    /// color of the terminal. Colors are the git names `red` or `brightred`, 0-255 or
+    /// `#rrggbb`. Attributes are `bold`, `dim`, `ul`, `blink`, `reverse`, `italic`, `strike`
+    /// and the other attribute names of this crate, like `overlined`. An attribute prefixed by
+    /// `no` or `no-` is turned off.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ansistream::{Attributes, Color, Style};
+    ///
+    /// let style: Style = "bold red on blue".parse().unwrap();
+    /// assert_eq!(Style::new().bold().fg(Color::Red).bg(Color::Blue), style);
+    ///
+    /// let style: Style = "208 ul no-italic".parse().unwrap();
+    /// assert_eq!(
+    ///     Style::new().fg(Color::Ansi256(208)).underline().negate(Attributes::ITALIC),
+    ///     style
+    /// );
+    /// assert_eq!("208 ul noitalic", style.to_string());
+    /// ```
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let error = |kind, word: &str| ParseStyleError {
+            kind,
+            position: word.as_ptr() as usize - spec.as_ptr() as usize,
+        };
+        let mut style = Style::new();
+        let mut colors = 0;
+        let mut words = spec.split_whitespace();
+        while let Some(word) = words.next() {
+            if word.eq_ignore_ascii_case("on") {
+                let color = words
+                    .next()
+                    .ok_or(error(ParseStyleErrorKind::MissingBackground, word))?;
+                if colors > 1 || style.background.is_some() {
+                    return Err(error(ParseStyleErrorKind::TooManyColors, color));
+                }
+                style.background = Color::from_git_name(color)
+                    .map(Some)
+                    .or_else(|| color.eq_ignore_ascii_case("normal").then_some(None))
+                    .ok_or(error(ParseStyleErrorKind::UnknownWord, color))?;
+                colors = 2;
+            } else if let Some(attr) = attribute_from_git_name(word) {
+                style.attributes.insert(attr);
+                style.negated.remove(attr);
+            } else if let Some(attr) = word
+                .strip_prefix("no-")
+                .or_else(|| word.strip_prefix("no"))
+                .and_then(attribute_from_git_name)
+            {
+                style.negated.insert(attr);
+                style.attributes.remove(attr);
+            } else {
+                let color = match Color::from_git_name(word) {
+                    Some(color) => Some(color),
+                    None if word.eq_ignore_ascii_case("normal") => None,
+                    None => return Err(error(ParseStyleErrorKind::UnknownWord, word)),
+                };
+                match colors {
+                    0 => style.foreground = color,
+                    1 => style.background = color,
+                    _ => return Err(error(ParseStyleErrorKind::TooManyColors, word)),
+                }
+                colors += 1;
+            }
+        }
+        Ok(style)
+    }
+}
+
+impl fmt::Display for Style {
+    /// Writes the style in the git config color syntax, which `Style::from_str` parses back.
+    /// An empty style is written as `normal`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return f.write_str("normal");
+        }
+        match (self.foreground, self.background) {
+            (Some(fg), Some(bg)) => write!(f, "{} {}", fg.git_name(), bg.git_name())?,
+            (Some(fg), None) => write!(f, "{}", fg.git_name())?,
+            (None, Some(bg)) => write!(f, "normal {}", bg.git_name())?,
+            (None, None) => {}
+        }
+        let mut separator = if self.foreground.is_some() || self.background.is_some() {
+            " "
+        } else {
+            ""
+        };
+        for (prefix, attributes) in [("", self.attributes), ("no", self.negated)] {
+            for (i, (attr, _, _)) in Attributes::TABLE.iter().enumerate() {
+                if attributes.contains(*attr) {
+                    let name = Attributes::GIT_NAMES
+                        .iter()
+                        .find(|(a, _)| a == attr)
+                        .map_or(Attributes::NAMES[i], |(_, name)| name);
+                    write!(f, "{separator}{prefix}{name}")?;
+                    separator = " ";
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_attributes_set() {
+        let mut attrs = Attributes::BOLD | Attributes::ITALIC;
+        assert!(attrs.contains(Attributes::BOLD));
+        assert!(!attrs.contains(Attributes::BOLD | Attributes::STRIKE));
+        attrs.remove(Attributes::BOLD);
+        attrs.insert(Attributes::STRIKE);
+        assert_eq!(Attributes::ITALIC | Attributes::STRIKE, attrs);
+        assert!(Attributes::empty().is_empty());
+        assert_eq!(Some(Attributes::OVERLINED), Attributes::from_code(53));
+        assert_eq!(None, Attributes::from_code(38));
+        assert_eq!(Some(Attributes::STRIKE), Attributes::from_name("strike"));
+        assert_eq!(None, Attributes::from_name("wobble"));
+    }
+
+    #[test]
+    fn test_style_builder() {
+        let style = Style::new().fg(Color::Red).underline().dim();
+        assert_eq!(Some(Color::Red), style.foreground);
+        assert_eq!(None, style.background);
+        assert_eq!(Attributes::UNDERLINE | Attributes::DIM, style.attributes);
+        assert!(!style.is_empty());
+        assert!(Style::default().is_empty());
+        assert!(!Style::new().negate(Attributes::BOLD).is_empty());
+    }
+
+    #[test]
+    fn test_parse_git_syntax() {
+        let parse = |spec: &str| spec.parse::<Style>().unwrap();
+        assert_eq!(
+            Style::new().bold().fg(Color::Red).bg(Color::Blue),
+            parse("bold red on blue")
+        );
+        assert_eq!(
+            Style::new().fg(Color::Red).bg(Color::Blue),
+            parse("red blue")
+        );
+        assert_eq!(Style::new().bg(Color::Blue), parse("normal blue"));
+        assert_eq!(
+            Style::new().fg(Color::Rgb(255, 136, 0)).italic(),
+            parse("#ff8800 italic")
+        );
+        assert_eq!(
+            Style::new()
+                .fg(Color::Default)
+                .invert()
+                .negate(Attributes::DIM),
+            parse("default reverse no-dim")
+        );
+        assert_eq!(Style::new(), parse(""));
+        assert_eq!(Style::new(), parse("normal"));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = |spec: &str| {
+            let err = spec.parse::<Style>().unwrap_err();
+            (err.kind(), err.position())
+        };
+        assert_eq!((ParseStyleErrorKind::UnknownWord, 5), error("bold purple"));
+        assert_eq!(
+            (ParseStyleErrorKind::TooManyColors, 9),
+            error("red blue green")
+        );
+        assert_eq!(
+            (ParseStyleErrorKind::TooManyColors, 12),
+            error("red on blue green")
+        );
+        assert_eq!((ParseStyleErrorKind::MissingBackground, 4), error("red on"));
+        assert_eq!(
+            "unknown attribute or color at byte 0",
+            "light_red".parse::<Style>().unwrap_err().to_string()
+        );
+    }
+
+    #[test]
+    fn test_from_sgr() {
+        let parse = |params: &str| Style::from_sgr(params).unwrap();
+        assert_eq!(Style::new().bold().fg(Color::Blue), parse("01;34"));
+        assert_eq!(
+            Style::new().fg(Color::Brown).bg(Color::DarkGray),
+            parse("40;33;100")
+        );
+        assert_eq!(
+            Style::new().fg(Color::Ansi256(208)).bg(Color::Default),
+            parse("38;5;208;49")
+        );
+        // 0 discards what is before it, reset codes negate
+        assert_eq!(Style::new().italic(), parse("1;31;0;3"));
+        assert_eq!(
+            Style::new()
+                .fg(Color::Red)
+                .negate(Attributes::BOLD | Attributes::DIM),
+            parse("1;31;22")
+        );
+        assert_eq!(Style::new().bold().negate(Attributes::DIM), parse("22;1"));
+        assert_eq!(Style::new(), parse(""));
+        let error = |params: &str| Style::from_sgr(params).unwrap_err().position();
+        assert_eq!(3, error("01;x"));
+        assert_eq!(0, error("38;5"));
+        assert_eq!(3, error("38;7;1"));
+        assert_eq!(2, error("1;99"));
+        assert_eq!(
+            "invalid SGR parameter at byte 0",
+            Style::from_sgr("-1").unwrap_err().to_string()
+        );
+    }
+
+    #[test]
+    fn test_display_round_trip() {
+        for spec in [
+            "normal",
+            "red",
+            "brightred blue",
+            "normal 236",
+            "#ff8800 default bold ul",
+            "reverse nobold nodim",
+            "overlined nostrike",
+        ] {
+            let style: Style = spec.parse().unwrap();
+            let text = style.to_string();
+            assert_eq!(spec, text);
+            assert_eq!(style, text.parse().unwrap());
+        }
+    }
+}
@@ -0,0 +1,229 @@
+//! Styles of `key=SGR` lists, like `GREP_COLORS` and `GCC_COLORS`.
+
+use std::env;
+
+use crate::{ParseStyleError, Style};
+
+/// The styles of grep when `GREP_COLORS` is not set.
+pub const GREP_DEFAULTS: &str = "ms=01;31:mc=01;31:sl=:cx=:fn=35:ln=32:bn=32:se=36";
+
+/// The styles of gcc when `GCC_COLORS` is not set.
+pub const GCC_DEFAULTS: &str = "error=01;31:warning=01;35:note=01;36:range1=32:range2=34:\
+    locus=01:quote=01:path=01;36:fixit-insert=32:fixit-delete=31:diff-filename=01:\
+    diff-hunk=32:diff-delete=31:diff-insert=32:type-diff=01;32";
+
+/// Styles by name, from a list of `key=SGR` entries separated by colons, like
+/// `ms=01;31:fn=35:ne`, the format of `GREP_COLORS` and `GCC_COLORS`. Entries without `=`,
+/// like `ne` of grep, are boolean flags. A key defined twice keeps the last style.
+///
+/// # Examples
+///
+/// ```
+/// use ansistream::{Color, Style, StyleMap};
+///
+/// let colors = StyleMap::parse("ms=01;31:fn=38;5;208:ne").unwrap();
+/// assert_eq!(Some(Style::new().bold().fg(Color::Red)), colors.get("ms"));
+/// assert_eq!(Some(Style::new().fg(Color::Ansi256(208))), colors.get("fn"));
+/// assert!(colors.flag("ne"));
+/// assert_eq!(12, StyleMap::parse("ms=01;31:fn=x").unwrap_err().position());
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct StyleMap {
+    /// The styles, in the order they were last defined.
+    styles: Vec<(String, Style)>,
+    flags: Vec<String>,
+}
+
+impl StyleMap {
+    /// Initializes an empty map.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parse a list of entries. Empty entries are ignored, and errors have the byte position
+    /// of the problem in spec.
+    pub fn parse(spec: &str) -> Result<StyleMap, ParseStyleError> {
+        let mut map = Self::new();
+        map.extend_parse(spec)?;
+        Ok(map)
+    }
+
+    /// Parse the list of the environment variable var. The map is empty if it is not set.
+    pub fn from_env(var: &str) -> Result<StyleMap, ParseStyleError> {
+        match env::var(var) {
+            Ok(spec) => Self::parse(&spec),
+            Err(_) => Ok(Self::new()),
+        }
+    }
+
+    /// Returns the styles of grep: `GREP_DEFAULTS`, overridden by `GREP_COLORS`. Like grep,
+    /// `mt` sets both `ms` and `mc`.
+    pub fn grep() -> Result<StyleMap, ParseStyleError> {
+        Self::grep_from(env::var("GREP_COLORS").ok().as_deref())
+    }
+
+    /// Returns the styles of grep for the value of `GREP_COLORS`, if it is set.
+    fn grep_from(spec: Option<&str>) -> Result<StyleMap, ParseStyleError> {
+        let mut map = Self::parse(GREP_DEFAULTS)?;
+        let user = Self::parse(spec.unwrap_or_default())?;
+        for (key, style) in user.iter() {
+            match key {
+                "mt" => {
+                    map.set("ms", style);
+                    map.set("mc", style);
+                }
+                key => map.set(key, style),
+            }
+        }
+        map.flags = user.flags;
+        Ok(map)
+    }
+
+    /// Returns the styles of gcc: `GCC_DEFAULTS`, overridden by `GCC_COLORS`. Like gcc, an
+    /// empty `GCC_COLORS` disables the colors, which gives an empty map.
+    pub fn gcc() -> Result<StyleMap, ParseStyleError> {
+        Self::gcc_from(env::var("GCC_COLORS").ok().as_deref())
+    }
+
+    /// Returns the styles of gcc for the value of `GCC_COLORS`, if it is set.
+    fn gcc_from(spec: Option<&str>) -> Result<StyleMap, ParseStyleError> {
+        if spec == Some("") {
+            return Ok(Self::new());
+        }
+        let mut map = Self::parse(GCC_DEFAULTS)?;
+        map.extend_parse(spec.unwrap_or_default())?;
+        Ok(map)
+    }
+
+    /// Parse a list of entries, which override the entries of the map.
+    pub fn extend_parse(&mut self, spec: &str) -> Result<(), ParseStyleError> {
+        let mut position = 0;
+        for entry in spec.split(':') {
+            let start = position;
+            position += entry.len() + 1;
+            match entry.split_once('=') {
+                Some((key, value)) => {
+                    let style =
+                        Style::from_sgr(value).map_err(|e| e.offset(start + key.len() + 1))?;
+                    self.set(key, style);
+                }
+                None if entry.is_empty() => {}
+                None => {
+                    if !self.flag(entry) {
+                        self.flags.push(entry.to_string());
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the style of key.
+    pub fn get(&self, key: &str) -> Option<Style> {
+        self.styles
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, style)| *style)
+    }
+
+    /// Returns the style of key, or an empty style if it is not defined.
+    pub fn style(&self, key: &str) -> Style {
+        self.get(key).unwrap_or_default()
+    }
+
+    /// Returns whether the flag key is set.
+    pub fn flag(&self, key: &str) -> bool {
+        self.flags.iter().any(|k| k == key)
+    }
+
+    /// Set the style of key.
+    pub fn set(&mut self, key: &str, style: Style) {
+        self.styles.retain(|(k, _)| k != key);
+        self.styles.push((key.to_string(), style));
+    }
+
+    /// Returns whether the map has neither styles nor flags.
+    pub fn is_empty(&self) -> bool {
+        self.styles.is_empty() && self.flags.is_empty()
+    }
+
+    /// Returns the keys and styles, in the order they were last defined.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, Style)> {
+        self.styles
+            .iter()
+            .map(|(key, style)| (key.as_str(), *style))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Color, ParseStyleErrorKind};
+
+    #[test]
+    fn test_parse() {
+        let map = StyleMap::parse("ms=01;31::sl=:fn=35:rv:ms=4:rv").unwrap();
+        assert_eq!(Some(Style::new().underline()), map.get("ms"));
+        // an empty value is an empty style, not a missing one
+        assert_eq!(Some(Style::new()), map.get("sl"));
+        assert_eq!(None, map.get("cx"));
+        assert_eq!(Style::new(), map.style("cx"));
+        assert!(map.flag("rv"));
+        assert!(!map.flag("ne"));
+        assert_eq!(
+            vec![
+                ("sl", Style::new()),
+                ("fn", Style::new().fg(Color::Magenta)),
+                ("ms", Style::new().underline())
+            ],
+            map.iter().collect::<Vec<_>>()
+        );
+        assert!(StyleMap::parse("").unwrap().is_empty());
+        let error = StyleMap::parse("error=01;31:note=01;38;2;1").unwrap_err();
+        assert_eq!(
+            (ParseStyleErrorKind::InvalidSgr, 20),
+            (error.kind(), error.position())
+        );
+    }
+
+    #[test]
+    fn test_defaults() {
+        let grep = StyleMap::parse(GREP_DEFAULTS).unwrap();
+        assert_eq!(Some(Style::new().fg(Color::Cyan)), grep.get("se"));
+        let gcc = StyleMap::parse(GCC_DEFAULTS).unwrap();
+        assert_eq!(
+            Some(Style::new().bold().fg(Color::Magenta)),
+            gcc.get("warning")
+        );
+        assert_eq!(15, gcc.iter().count());
+    }
+    #[test]
+    fn test_grep_merge() {
+        let defaults = StyleMap::grep_from(None).unwrap();
+        assert_eq!(StyleMap::parse(GREP_DEFAULTS).unwrap(), defaults);
+        let map = StyleMap::grep_from(Some("mt=04:fn=34:ne")).unwrap();
+        assert_eq!(Some(Style::new().underline()), map.get("ms"));
+        assert_eq!(Some(Style::new().underline()), map.get("mc"));
+        assert_eq!(Some(Style::new().fg(Color::Blue)), map.get("fn"));
+        // entries which are not overridden keep their default
+        assert_eq!(Some(Style::new().fg(Color::Cyan)), map.get("se"));
+        assert!(map.flag("ne"));
+        assert_eq!(None, map.get("mt"));
+        assert_eq!(defaults, StyleMap::grep_from(Some("")).unwrap());
+    }
+
+    #[test]
+    fn test_gcc_merge() {
+        let defaults = StyleMap::gcc_from(None).unwrap();
+        assert_eq!(StyleMap::parse(GCC_DEFAULTS).unwrap(), defaults);
+        let map = StyleMap::gcc_from(Some("error=04:locus=")).unwrap();
+        assert_eq!(Some(Style::new().underline()), map.get("error"));
+        assert_eq!(Some(Style::new()), map.get("locus"));
+        // entries which are not overridden keep their default
+        assert_eq!(defaults.get("warning"), map.get("warning"));
+        assert_eq!(15, map.iter().count());
+        assert!(StyleMap::gcc_from(Some("")).unwrap().is_empty());
+        let error = StyleMap::gcc_from(Some("error=x")).unwrap_err();
+        assert_eq!(6, error.position());
+    }
+}
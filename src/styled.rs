@@ -0,0 +1,310 @@
+//! Display wrapper which writes a value surrounded by the escape codes of a style.
+
+use core::{
+    fmt::{self, Display},
+    sync::atomic::{AtomicBool, Ordering},
+};
+#[cfg(feature = "std")]
+use std::cell::Cell;
+
+use crate::{
+    encode::{self, FmtSink},
+    Attributes, Color, ColorEncoding, Style,
+};
+
+static COLORS_ENABLED: AtomicBool = AtomicBool::new(true);
+
+#[cfg(feature = "std")]
+thread_local! {
+    static THREAD_COLORS_ENABLED: Cell<Option<bool>> = const { Cell::new(None) };
+}
+
+/// Enable or disable the escape codes written by `Styled` values, for every thread.
+/// When disabled, a `Styled` value displays only its inner value.
+pub fn set_colors_enabled(enabled: bool) {
+    COLORS_ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// Override the global switch for the current thread only. `None` follows the global switch again.
+#[cfg(feature = "std")]
+pub fn set_thread_colors_enabled(enabled: Option<bool>) {
+    THREAD_COLORS_ENABLED.with(|cell| cell.set(enabled));
+}
+
+/// Returns true if `Styled` values write escape codes on the current thread. Colors are
+/// enabled by default.
+pub fn colors_enabled() -> bool {
+    #[cfg(feature = "std")]
+    if let Some(enabled) = THREAD_COLORS_ENABLED.with(Cell::get) {
+        return enabled;
+    }
+    COLORS_ENABLED.load(Ordering::Relaxed)
+}
+
+/// A value displayed with a style. The escape codes are written around the inner value
+/// directly to the formatter, so displaying it does not allocate.
+///
+/// # Examples
+///
+/// ```
+/// use ansistream::{Color, Stylize};
+///
+/// let text = format!("{}: {}", "error".red().bold(), 42.fg(Color::Rgb(255, 128, 0)));
+/// assert_eq!(
+///     "\x1b[1;31merror\x1b[22;39m: \x1b[38;2;255;128;0m42\x1b[39m",
+///     text
+/// );
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Styled<T> {
+    value: T,
+    style: Style,
+}
+
+impl<T> Styled<T> {
+    /// Wrap value with a style.
+    pub fn new(value: T, style: Style) -> Self {
+        Styled { value, style }
+    }
+
+    /// Returns the style of this value.
+    pub fn style(&self) -> &Style {
+        &self.style
+    }
+
+    /// Returns the inner value.
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<T: Display> Display for Styled<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.style.is_empty() || !colors_enabled() {
+            return self.value.fmt(f);
+        }
+        encode::write_style(&mut FmtSink(f), &self.style, ColorEncoding::Semicolon)?;
+        self.value.fmt(f)?;
+        encode::reset_style(&mut FmtSink(f), &self.style)
+    }
+}
+
+/// Declares the fluent style methods once for the `Stylize` trait, which wraps any value,
+/// and once for `Styled`, which updates its style instead of wrapping it again.
+macro_rules! fluent_methods {
+    ($($(#[$doc:meta])* $name:ident => $method:ident($arg:expr);)*) => {
+        /// Fluent methods which wrap any `Display` value in a `Styled` value.
+        pub trait Stylize: Display + Sized {
+            /// Wrap the value with a style.
+            fn with_style(self, style: Style) -> Styled<Self> {
+                Styled::new(self, style)
+            }
+
+            /// Set the foreground color.
+            fn fg(self, color: Color) -> Styled<Self> {
+                Styled::new(self, Style::new().fg(color))
+            }
+
+            /// Set the background color.
+            fn bg(self, color: Color) -> Styled<Self> {
+                Styled::new(self, Style::new().bg(color))
+            }
+
+            /// Add text attributes.
+            fn attr(self, attributes: Attributes) -> Styled<Self> {
+                Styled::new(self, Style::new().attr(attributes))
+            }
+
+            $(
+                $(#[$doc])*
+                fn $name(self) -> Styled<Self> {
+                    self.$method($arg)
+                }
+            )*
+        }
+
+        impl<T> Styled<T> {
+            /// Replace the style.
+            pub fn with_style(mut self, style: Style) -> Self {
+                self.style = style;
+                self
+            }
+
+            /// Set the foreground color.
+            pub fn fg(mut self, color: Color) -> Self {
+                self.style = self.style.fg(color);
+                self
+            }
+
+            /// Set the background color.
+            pub fn bg(mut self, color: Color) -> Self {
+                self.style = self.style.bg(color);
+                self
+            }
+
+            /// Add text attributes.
+            pub fn attr(mut self, attributes: Attributes) -> Self {
+                self.style = self.style.attr(attributes);
+                self
+            }
+
+            $(
+                $(#[$doc])*
+                pub fn $name(self) -> Self {
+                    self.$method($arg)
+                }
+            )*
+        }
+    };
+}
+
+fluent_methods! {
+    /// Set the foreground color to black.
+    black => fg(Color::Black);
+    /// Set the foreground color to red.
+    red => fg(Color::Red);
+    /// Set the foreground color to green.
+    green => fg(Color::Green);
+    /// Set the foreground color to brown.
+    brown => fg(Color::Brown);
+    /// Set the foreground color to blue.
+    blue => fg(Color::Blue);
+    /// Set the foreground color to magenta.
+    magenta => fg(Color::Magenta);
+    /// Set the foreground color to cyan.
+    cyan => fg(Color::Cyan);
+    /// Set the foreground color to light gray.
+    light_gray => fg(Color::LightGray);
+    /// Set the foreground color to dark gray.
+    dark_gray => fg(Color::DarkGray);
+    /// Set the foreground color to light red.
+    light_red => fg(Color::LightRed);
+    /// Set the foreground color to light green.
+    light_green => fg(Color::LightGreen);
+    /// Set the foreground color to yellow.
+    yellow => fg(Color::Yellow);
+    /// Set the foreground color to light blue.
+    light_blue => fg(Color::LightBlue);
+    /// Set the foreground color to light magenta.
+    light_magenta => fg(Color::LightMagenta);
+    /// Set the foreground color to light cyan.
+    light_cyan => fg(Color::LightCyan);
+    /// Set the foreground color to white.
+    white => fg(Color::White);
+    /// Set the background color to black.
+    on_black => bg(Color::Black);
+    /// Set the background color to red.
+    on_red => bg(Color::Red);
+    /// Set the background color to green.
+    on_green => bg(Color::Green);
+    /// Set the background color to brown.
+    on_brown => bg(Color::Brown);
+    /// Set the background color to blue.
+    on_blue => bg(Color::Blue);
+    /// Set the background color to magenta.
+    on_magenta => bg(Color::Magenta);
+    /// Set the background color to cyan.
+    on_cyan => bg(Color::Cyan);
+    /// Set the background color to light gray.
+    on_light_gray => bg(Color::LightGray);
+    /// Set the background color to dark gray.
+    on_dark_gray => bg(Color::DarkGray);
+    /// Set the background color to light red.
+    on_light_red => bg(Color::LightRed);
+    /// Set the background color to light green.
+    on_light_green => bg(Color::LightGreen);
+    /// Set the background color to yellow.
+    on_yellow => bg(Color::Yellow);
+    /// Set the background color to light blue.
+    on_light_blue => bg(Color::LightBlue);
+    /// Set the background color to light magenta.
+    on_light_magenta => bg(Color::LightMagenta);
+    /// Set the background color to light cyan.
+    on_light_cyan => bg(Color::LightCyan);
+    /// Set the background color to white.
+    on_white => bg(Color::White);
+    /// Add the bold attribute.
+    bold => attr(Attributes::BOLD);
+    /// Add the dim attribute.
+    dim => attr(Attributes::DIM);
+    /// Add the italic attribute.
+    italic => attr(Attributes::ITALIC);
+    /// Add the underline attribute.
+    underline => attr(Attributes::UNDERLINE);
+    /// Add the blink attribute.
+    blink => attr(Attributes::BLINK);
+    /// Add the invert attribute.
+    invert => attr(Attributes::INVERT);
+    /// Add the hidden attribute.
+    hidden => attr(Attributes::HIDDEN);
+    /// Add the strike attribute.
+    strike => attr(Attributes::STRIKE);
+}
+
+impl<T: Display> Stylize for T {}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_styled_display() {
+        assert_eq!("\x1b[31mred\x1b[39m", "red".red().to_string());
+        assert_eq!(
+            "\x1b[1;3;48;5;236m7\x1b[22;23;49m",
+            7.bold().italic().bg(Color::Ansi256(236)).to_string()
+        );
+        // format flags are applied to the inner value
+        assert_eq!("\x1b[4m  x\x1b[24m", format!("{:>3}", "x".underline()));
+        // an empty style writes only the value
+        assert_eq!("x", "x".with_style(Style::new()).to_string());
+    }
+
+    #[test]
+    fn test_styled_flattens_style() {
+        let styled = "x".red().on_white().bold();
+        assert_eq!(
+            &Style::new().fg(Color::Red).bg(Color::White).bold(),
+            styled.style()
+        );
+        assert_eq!("x", styled.into_inner());
+    }
+
+    #[test]
+    fn test_named_color_methods() {
+        type Method = fn(&'static str) -> Styled<&'static str>;
+        let methods: [(Method, Method); 16] = [
+            (Stylize::black, Stylize::on_black),
+            (Stylize::red, Stylize::on_red),
+            (Stylize::green, Stylize::on_green),
+            (Stylize::brown, Stylize::on_brown),
+            (Stylize::blue, Stylize::on_blue),
+            (Stylize::magenta, Stylize::on_magenta),
+            (Stylize::cyan, Stylize::on_cyan),
+            (Stylize::light_gray, Stylize::on_light_gray),
+            (Stylize::dark_gray, Stylize::on_dark_gray),
+            (Stylize::light_red, Stylize::on_light_red),
+            (Stylize::light_green, Stylize::on_light_green),
+            (Stylize::yellow, Stylize::on_yellow),
+            (Stylize::light_blue, Stylize::on_light_blue),
+            (Stylize::light_magenta, Stylize::on_light_magenta),
+            (Stylize::light_cyan, Stylize::on_light_cyan),
+            (Stylize::white, Stylize::on_white),
+        ];
+        for (color, (fg, bg)) in Color::NAMED.into_iter().zip(methods) {
+            assert_eq!(&Style::new().fg(color), fg("x").style());
+            assert_eq!(&Style::new().bg(color), bg("x").style());
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_thread_colors_enabled_switch() {
+        set_thread_colors_enabled(Some(false));
+        assert!(!colors_enabled());
+        assert_eq!("x", "x".red().to_string());
+        set_thread_colors_enabled(None);
+        assert!(colors_enabled());
+        assert_eq!("\x1b[31mx\x1b[39m", "x".red().to_string());
+    }
+}
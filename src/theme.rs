@@ -0,0 +1,347 @@
+//! Styles of semantic roles, so every output of a program is restyled in one place.
+
+use core::fmt;
+
+use crate::{Color, Style};
+
+/// What a piece of text means, which a `Theme` maps to a style.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Role {
+    Error,
+    Warning,
+    Info,
+    Success,
+    /// Secondary text, like timestamps and hints.
+    Muted,
+    /// Text to draw attention to, like the current item.
+    Accent,
+    Heading,
+    /// Code, commands and paths.
+    Code,
+    Link,
+    /// Text highlighted with a background, like search matches.
+    Highlight,
+}
+
+impl Role {
+    /// Number of roles.
+    pub const COUNT: usize = 10;
+
+    /// All roles, in declaration order.
+    pub const ALL: [Role; Role::COUNT] = [
+        Role::Error,
+        Role::Warning,
+        Role::Info,
+        Role::Success,
+        Role::Muted,
+        Role::Accent,
+        Role::Heading,
+        Role::Code,
+        Role::Link,
+        Role::Highlight,
+    ];
+
+    /// Returns the lowercase name of the role, like `error`.
+    pub const fn name(self) -> &'static str {
+        match self {
+            Role::Error => "error",
+            Role::Warning => "warning",
+            Role::Info => "info",
+            Role::Success => "success",
+            Role::Muted => "muted",
+            Role::Accent => "accent",
+            Role::Heading => "heading",
+            Role::Code => "code",
+            Role::Link => "link",
+            Role::Highlight => "highlight",
+        }
+    }
+
+    /// Returns the role with a name, ignoring case.
+    pub fn from_name(name: &str) -> Option<Role> {
+        Role::ALL
+            .into_iter()
+            .find(|role| role.name().eq_ignore_ascii_case(name))
+    }
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// The style of every `Role`. Built-in themes are associated constants, the default is
+/// `Theme::BASIC`, which only uses the 16 named colors.
+///
+/// # Examples
+///
+/// ```
+/// # #[cfg(feature = "std")] {
+/// use ansistream::{AnsiEscapeStream, Color, Role, Style, Theme};
+/// use std::io::Cursor;
+///
+/// let theme = Theme::NORD.with(Role::Code, Style::new().fg(Color::White).bold());
+/// let mut astream = AnsiEscapeStream::new(Cursor::new(Vec::<u8>::new()));
+/// astream.set_theme(theme);
+/// astream.write_role(Role::Code, "cargo").unwrap();
+/// assert_eq!(b"\x1b[1;97mcargo\x1b[22;39m", astream.buffer());
+/// assert_eq!(Some(Theme::DRACULA), Theme::by_name("dracula"));
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Theme {
+    styles: [Style; Role::COUNT],
+}
+
+/// Returns a RGB color of a hex value, like 0xff8000.
+const fn hex(value: u32) -> Color {
+    Color::Rgb((value >> 16) as u8, (value >> 8) as u8, value as u8)
+}
+
+impl Theme {
+    /// The default theme, with the named colors, so it follows the palette of the terminal.
+    pub const BASIC: Theme = Theme::new()
+        .with(Role::Error, Style::new().fg(Color::LightRed).bold())
+        .with(Role::Warning, Style::new().fg(Color::Yellow))
+        .with(Role::Info, Style::new().fg(Color::LightBlue))
+        .with(Role::Success, Style::new().fg(Color::LightGreen))
+        .with(Role::Muted, Style::new().fg(Color::DarkGray))
+        .with(Role::Accent, Style::new().fg(Color::LightMagenta))
+        .with(Role::Heading, Style::new().bold().underline())
+        .with(Role::Code, Style::new().fg(Color::LightCyan))
+        .with(Role::Link, Style::new().fg(Color::LightBlue).underline())
+        .with(Role::Highlight, Style::new().invert());
+
+    /// Solarized for dark backgrounds.
+    pub const SOLARIZED_DARK: Theme = Theme::solarized(0x586e75, 0x93a1a1, 0x073642);
+    /// Solarized for light backgrounds.
+    pub const SOLARIZED_LIGHT: Theme = Theme::solarized(0x93a1a1, 0x586e75, 0xeee8d5);
+
+    /// Gruvbox for dark backgrounds.
+    pub const GRUVBOX_DARK: Theme = Theme::new()
+        .with(Role::Error, Style::new().fg(hex(0xfb4934)).bold())
+        .with(Role::Warning, Style::new().fg(hex(0xfabd2f)))
+        .with(Role::Info, Style::new().fg(hex(0x83a598)))
+        .with(Role::Success, Style::new().fg(hex(0xb8bb26)))
+        .with(Role::Muted, Style::new().fg(hex(0x928374)))
+        .with(Role::Accent, Style::new().fg(hex(0xfe8019)))
+        .with(Role::Heading, Style::new().fg(hex(0xebdbb2)).bold())
+        .with(Role::Code, Style::new().fg(hex(0x8ec07c)))
+        .with(Role::Link, Style::new().fg(hex(0xd3869b)).underline())
+        .with(
+            Role::Highlight,
+            Style::new().fg(hex(0xebdbb2)).bg(hex(0x504945)),
+        );
+
+    /// Gruvbox for light backgrounds.
+    pub const GRUVBOX_LIGHT: Theme = Theme::new()
+        .with(Role::Error, Style::new().fg(hex(0x9d0006)).bold())
+        .with(Role::Warning, Style::new().fg(hex(0xb57614)))
+        .with(Role::Info, Style::new().fg(hex(0x076678)))
+        .with(Role::Success, Style::new().fg(hex(0x79740e)))
+        .with(Role::Muted, Style::new().fg(hex(0x928374)))
+        .with(Role::Accent, Style::new().fg(hex(0xaf3a03)))
+        .with(Role::Heading, Style::new().fg(hex(0x3c3836)).bold())
+        .with(Role::Code, Style::new().fg(hex(0x427b58)))
+        .with(Role::Link, Style::new().fg(hex(0x8f3f71)).underline())
+        .with(
+            Role::Highlight,
+            Style::new().fg(hex(0x3c3836)).bg(hex(0xd5c4a1)),
+        );
+
+    /// Dracula, a dark theme.
+    pub const DRACULA: Theme = Theme::new()
+        .with(Role::Error, Style::new().fg(hex(0xff5555)).bold())
+        .with(Role::Warning, Style::new().fg(hex(0xffb86c)))
+        .with(Role::Info, Style::new().fg(hex(0x8be9fd)))
+        .with(Role::Success, Style::new().fg(hex(0x50fa7b)))
+        .with(Role::Muted, Style::new().fg(hex(0x6272a4)))
+        .with(Role::Accent, Style::new().fg(hex(0xff79c6)))
+        .with(Role::Heading, Style::new().fg(hex(0xbd93f9)).bold())
+        .with(Role::Code, Style::new().fg(hex(0xf1fa8c)))
+        .with(Role::Link, Style::new().fg(hex(0x8be9fd)).underline())
+        .with(
+            Role::Highlight,
+            Style::new().fg(hex(0xf8f8f2)).bg(hex(0x44475a)),
+        );
+
+    /// Nord, a dark theme.
+    pub const NORD: Theme = Theme::new()
+        .with(Role::Error, Style::new().fg(hex(0xbf616a)).bold())
+        .with(Role::Warning, Style::new().fg(hex(0xebcb8b)))
+        .with(Role::Info, Style::new().fg(hex(0x81a1c1)))
+        .with(Role::Success, Style::new().fg(hex(0xa3be8c)))
+        .with(Role::Muted, Style::new().fg(hex(0x616e88)))
+        .with(Role::Accent, Style::new().fg(hex(0xb48ead)))
+        .with(Role::Heading, Style::new().fg(hex(0x88c0d0)).bold())
+        .with(Role::Code, Style::new().fg(hex(0x8fbcbb)))
+        .with(Role::Link, Style::new().fg(hex(0x88c0d0)).underline())
+        .with(
+            Role::Highlight,
+            Style::new().fg(hex(0xeceff4)).bg(hex(0x434c5e)),
+        );
+
+    /// Pure colors and bold text for dark backgrounds.
+    pub const HIGH_CONTRAST_DARK: Theme = Theme::new()
+        .with(Role::Error, Style::new().fg(hex(0xff4040)).bold())
+        .with(Role::Warning, Style::new().fg(hex(0xffff00)).bold())
+        .with(Role::Info, Style::new().fg(hex(0x00ffff)))
+        .with(Role::Success, Style::new().fg(hex(0x00ff00)))
+        .with(Role::Muted, Style::new().fg(hex(0xc0c0c0)))
+        .with(Role::Accent, Style::new().fg(hex(0xff80ff)).bold())
+        .with(
+            Role::Heading,
+            Style::new().fg(hex(0xffffff)).bold().underline(),
+        )
+        .with(Role::Code, Style::new().fg(hex(0xffffff)).bold())
+        .with(Role::Link, Style::new().fg(hex(0x80c0ff)).underline())
+        .with(
+            Role::Highlight,
+            Style::new().fg(hex(0x000000)).bg(hex(0xffff00)),
+        );
+
+    /// Dark colors and bold text for light backgrounds.
+    pub const HIGH_CONTRAST_LIGHT: Theme = Theme::new()
+        .with(Role::Error, Style::new().fg(hex(0xa00000)).bold())
+        .with(Role::Warning, Style::new().fg(hex(0x804000)).bold())
+        .with(Role::Info, Style::new().fg(hex(0x0000a0)))
+        .with(Role::Success, Style::new().fg(hex(0x006000)))
+        .with(Role::Muted, Style::new().fg(hex(0x404040)))
+        .with(Role::Accent, Style::new().fg(hex(0x800080)).bold())
+        .with(
+            Role::Heading,
+            Style::new().fg(hex(0x000000)).bold().underline(),
+        )
+        .with(Role::Code, Style::new().fg(hex(0x000000)).bold())
+        .with(Role::Link, Style::new().fg(hex(0x0000c0)).underline())
+        .with(
+            Role::Highlight,
+            Style::new().fg(hex(0x000000)).bg(hex(0xffff00)),
+        );
+
+    /// The Okabe-Ito palette for dark backgrounds, whose colors stay distinct with the common
+    /// color vision deficiencies. Errors are also bold, so they do not depend on color.
+    pub const CVD_SAFE: Theme = Theme::new()
+        .with(Role::Error, Style::new().fg(hex(0xd55e00)).bold())
+        .with(Role::Warning, Style::new().fg(hex(0xe69f00)))
+        .with(Role::Info, Style::new().fg(hex(0x56b4e9)))
+        .with(Role::Success, Style::new().fg(hex(0x009e73)))
+        .with(Role::Muted, Style::new().fg(hex(0x999999)))
+        .with(Role::Accent, Style::new().fg(hex(0xcc79a7)))
+        .with(Role::Heading, Style::new().bold().underline())
+        .with(Role::Code, Style::new().fg(hex(0xf0e442)))
+        .with(Role::Link, Style::new().fg(hex(0x56b4e9)).underline())
+        .with(
+            Role::Highlight,
+            Style::new().fg(hex(0x000000)).bg(hex(0xe69f00)),
+        );
+
+    /// The built-in themes and their names.
+    pub const BUILTIN: [(&'static str, Theme); 10] = [
+        ("basic", Theme::BASIC),
+        ("solarized-dark", Theme::SOLARIZED_DARK),
+        ("solarized-light", Theme::SOLARIZED_LIGHT),
+        ("gruvbox-dark", Theme::GRUVBOX_DARK),
+        ("gruvbox-light", Theme::GRUVBOX_LIGHT),
+        ("dracula", Theme::DRACULA),
+        ("nord", Theme::NORD),
+        ("high-contrast-dark", Theme::HIGH_CONTRAST_DARK),
+        ("high-contrast-light", Theme::HIGH_CONTRAST_LIGHT),
+        ("cvd-safe", Theme::CVD_SAFE),
+    ];
+
+    /// Returns a theme with empty styles, which writes text without escape codes.
+    pub const fn new() -> Self {
+        Theme {
+            styles: [Style::new(); Role::COUNT],
+        }
+    }
+
+    /// Returns the built-in theme with a name, like `solarized-light`, ignoring case.
+    pub fn by_name(name: &str) -> Option<Theme> {
+        Theme::BUILTIN
+            .into_iter()
+            .find(|(builtin, _)| builtin.eq_ignore_ascii_case(name))
+            .map(|(_, theme)| theme)
+    }
+
+    /// Returns the style of a role.
+    pub const fn style(&self, role: Role) -> Style {
+        self.styles[role as usize]
+    }
+
+    /// Set the style of a role.
+    pub fn set(&mut self, role: Role, style: Style) {
+        self.styles[role as usize] = style;
+    }
+
+    /// Returns the theme with the style of a role replaced.
+    pub const fn with(mut self, role: Role, style: Style) -> Self {
+        self.styles[role as usize] = style;
+        self
+    }
+
+    /// Solarized with the colors of the muted and heading roles, and of the highlight
+    /// background, which differ between the dark and light variants.
+    const fn solarized(muted: u32, heading: u32, highlight: u32) -> Theme {
+        Theme::new()
+            .with(Role::Error, Style::new().fg(hex(0xdc322f)).bold())
+            .with(Role::Warning, Style::new().fg(hex(0xb58900)))
+            .with(Role::Info, Style::new().fg(hex(0x268bd2)))
+            .with(Role::Success, Style::new().fg(hex(0x859900)))
+            .with(Role::Muted, Style::new().fg(hex(muted)))
+            .with(Role::Accent, Style::new().fg(hex(0xd33682)))
+            .with(Role::Heading, Style::new().fg(hex(heading)).bold())
+            .with(Role::Code, Style::new().fg(hex(0x2aa198)))
+            .with(Role::Link, Style::new().fg(hex(0x6c71c4)).underline())
+            .with(
+                Role::Highlight,
+                Style::new().fg(hex(0xcb4b16)).bg(hex(highlight)),
+            )
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::BASIC
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roles() {
+        for (i, role) in Role::ALL.into_iter().enumerate() {
+            assert_eq!(i, role as usize);
+            assert_eq!(Some(role), Role::from_name(role.name()));
+        }
+        assert_eq!(Some(Role::Warning), Role::from_name("WARNING"));
+        assert_eq!(None, Role::from_name("fatal"));
+    }
+
+    #[test]
+    fn test_builtin() {
+        for (name, theme) in Theme::BUILTIN {
+            assert_eq!(Some(theme), Theme::by_name(name));
+            // every role of a built-in theme is styled
+            for role in Role::ALL {
+                assert!(!theme.style(role).is_empty(), "{name} {role}");
+            }
+        }
+        assert_eq!(Some(Theme::NORD), Theme::by_name("Nord"));
+        assert_eq!(None, Theme::by_name("monokai"));
+        assert_ne!(Theme::SOLARIZED_DARK, Theme::SOLARIZED_LIGHT);
+    }
+
+    #[test]
+    fn test_set() {
+        let mut theme = Theme::default();
+        assert_eq!(Theme::BASIC, theme);
+        theme.set(Role::Error, Style::new().fg(Color::Red));
+        assert_eq!(Style::new().fg(Color::Red), theme.style(Role::Error));
+        assert_eq!(Style::new(), Theme::new().style(Role::Link));
+    }
+}
@@ -0,0 +1,376 @@
+//! Extension trait which writes ansi escape codes to any `io::Write`.
+
+use std::{
+    fmt,
+    io::{self, Write},
+};
+
+use crate::{
+    encode::{self, IoSink},
+    markup, ColorEncoding, Error, Result, Style, BC_RICH_COLORS, FC_RICH_COLORS,
+};
+
+const SEMICOLON: ColorEncoding = ColorEncoding::Semicolon;
+
+/// Writes ansi escape codes directly to any writer, without the `AnsiEscapeStream` buffer.
+/// It is implemented for every `io::Write`, so escape codes can be written to a `Vec<u8>`,
+/// a `StdoutLock`, a socket or any custom buffer.
+///
+/// The trait has no state, so it can not hold a `ColorEncoding` like `AnsiEscapeStream` does:
+/// the text, style and markup methods always write rich colors with
+/// `ColorEncoding::Semicolon`. Only `write_color_256` and `write_color_rgb` take the encoding
+/// as a parameter. Use `AnsiEscapeStream::set_color_encoding` for terminals which need the
+/// colon forms.
+///
+/// # Examples
+///
+/// ```
+/// use ansistream::{AnsiWrite, FC_RED};
+///
+/// let mut buffer = Vec::<u8>::new();
+/// buffer.write_attribute(FC_RED).unwrap();
+/// buffer.cursor_up(2).unwrap();
+/// assert_eq!(b"\x1b[31m\x1b[2A", buffer.as_slice());
+/// ```
+pub trait AnsiWrite: Write {
+    /// Reset all ansi escape code attributes using ESC[0m.
+    fn reset_all_attributes(&mut self) -> io::Result<()> {
+        encode::reset_all_attributes(&mut IoSink(self))
+    }
+
+    /// Reset an attribute of type(FC, BC, TS). If the attribute is a foreground color, then
+    /// reset it to the default foreground color, and so on.
+    fn reset_attribute(&mut self, attr: u16) -> io::Result<()> {
+        encode::reset_attribute(&mut IoSink(self), attr)
+    }
+
+    /// Write an attribute.
+    fn write_attribute(&mut self, attr: u16) -> io::Result<()> {
+        encode::write_attribute(&mut IoSink(self), attr)
+    }
+
+    /// Write an attribute, rejecting values that are not known SGR attributes
+    /// or that require additional parameters, like FC_RICH_COLORS.
+    fn try_write_attribute(&mut self, attr: u16) -> Result<()> {
+        self.write_attribute(Error::check_attribute(attr)?)?;
+        Ok(())
+    }
+
+    /// Select one of the alternative fonts (SGR 11-19). Font 0 restores the primary font,
+    /// any value above 9 is clamped to the last alternative font.
+    fn write_font(&mut self, font: u8) -> io::Result<()> {
+        encode::write_font(&mut IoSink(self), font)
+    }
+
+    /// Write a 256 color attribute. attr is FC_RICH_COLORS or BC_RICH_COLORS.
+    fn write_color_256(
+        &mut self,
+        attr: u16,
+        color: u16,
+        encoding: ColorEncoding,
+    ) -> io::Result<()> {
+        encode::write_color_256(&mut IoSink(self), attr, color, encoding)
+    }
+
+    /// Write a RGB color attribute. attr is FC_RICH_COLORS or BC_RICH_COLORS.
+    fn write_color_rgb(
+        &mut self,
+        attr: u16,
+        r: u16,
+        g: u16,
+        b: u16,
+        encoding: ColorEncoding,
+    ) -> io::Result<()> {
+        encode::write_color_rgb(&mut IoSink(self), attr, (r, g, b), encoding)
+    }
+
+    /// Write a 16 formatted foreground color text. The attribute is reseted at the end of operation.
+    fn write_text_fc_fmt(&mut self, color: u16, fmt: fmt::Arguments<'_>) -> io::Result<()> {
+        encode::write_text_fc_fmt(&mut IoSink(self), color, fmt)
+    }
+
+    /// Write a 16 formatted background color text. The attribute is reseted at the end of operation.
+    fn write_text_bc_fmt(&mut self, color: u16, fmt: fmt::Arguments<'_>) -> io::Result<()> {
+        encode::write_text_bc_fmt(&mut IoSink(self), color, fmt)
+    }
+
+    /// Write 16 foregournd and background color text. If the text is
+    /// empty, the reset operation will not be performed. Only the codes from foreground
+    /// and backround color are reseted.
+    fn write_text_color(&mut self, foreground: u16, background: u16, text: &str) -> io::Result<()> {
+        encode::write_text_color(&mut IoSink(self), foreground, background, text)
+    }
+
+    /// Write 16 foreground and background color formatted text.
+    /// Only the codes from foreground and background are reset.
+    fn write_text_color_fmt(
+        &mut self,
+        foreground: u16,
+        background: u16,
+        fmt: fmt::Arguments<'_>,
+    ) -> io::Result<()> {
+        encode::write_text_color_fmt(&mut IoSink(self), foreground, background, fmt)
+    }
+
+    /// Write 256 foreground color text. If the text is empty, the
+    /// reset operation will not be performed.
+    fn write_text_fc256(&mut self, color: u16, text: &str) -> io::Result<()> {
+        encode::write_text_256(&mut IoSink(self), FC_RICH_COLORS, color, SEMICOLON, text)
+    }
+
+    /// Write 256 foreground color formatted text. A partial reset operation of used codes
+    /// will be performed.
+    fn write_text_fc256_fmt(&mut self, color: u16, fmt: fmt::Arguments<'_>) -> io::Result<()> {
+        encode::write_text_256_fmt(&mut IoSink(self), FC_RICH_COLORS, color, SEMICOLON, fmt)
+    }
+
+    /// Write 256 background color text. If the text is empty, the
+    /// reset operation will not be performed.
+    fn write_text_bc256(&mut self, color: u16, text: &str) -> io::Result<()> {
+        encode::write_text_256(&mut IoSink(self), BC_RICH_COLORS, color, SEMICOLON, text)
+    }
+
+    /// Write 256 background color formatted text. A partial reset operation of used codes
+    /// will be performed.
+    fn write_text_bc256_fmt(&mut self, color: u16, fmt: fmt::Arguments<'_>) -> io::Result<()> {
+        encode::write_text_256_fmt(&mut IoSink(self), BC_RICH_COLORS, color, SEMICOLON, fmt)
+    }
+
+    /// Write RGB foreground color text. If the text is empty, the
+    /// reset operation will not be performed.
+    fn write_text_fcrgb(&mut self, r: u16, g: u16, b: u16, text: &str) -> io::Result<()> {
+        encode::write_text_rgb(
+            &mut IoSink(self),
+            FC_RICH_COLORS,
+            (r, g, b),
+            SEMICOLON,
+            text,
+        )
+    }
+
+    /// Write RGB formatted foreground color text. A partial reset operation
+    /// is performed.
+    fn write_text_fcrgb_fmt(
+        &mut self,
+        r: u16,
+        g: u16,
+        b: u16,
+        fmt: fmt::Arguments<'_>,
+    ) -> io::Result<()> {
+        encode::write_text_rgb_fmt(&mut IoSink(self), FC_RICH_COLORS, (r, g, b), SEMICOLON, fmt)
+    }
+
+    /// Write RGB background color text. If the text is empty, the
+    /// reset operation will not be performed.
+    fn write_text_bcrgb(&mut self, r: u16, g: u16, b: u16, text: &str) -> io::Result<()> {
+        encode::write_text_rgb(
+            &mut IoSink(self),
+            BC_RICH_COLORS,
+            (r, g, b),
+            SEMICOLON,
+            text,
+        )
+    }
+
+    /// Write formatted RGB background color text. A partial reset operation is performed.
+    fn write_text_bcrgb_fmt(
+        &mut self,
+        r: u16,
+        g: u16,
+        b: u16,
+        fmt: fmt::Arguments<'_>,
+    ) -> io::Result<()> {
+        encode::write_text_rgb_fmt(&mut IoSink(self), BC_RICH_COLORS, (r, g, b), SEMICOLON, fmt)
+    }
+
+    /// Write the attributes and colors of a style as a single SGR sequence.
+    fn write_style(&mut self, style: &Style) -> io::Result<()> {
+        encode::write_style(&mut IoSink(self), style, SEMICOLON)
+    }
+
+    /// Reset only the attributes and colors used by a style.
+    fn reset_style(&mut self, style: &Style) -> io::Result<()> {
+        encode::reset_style(&mut IoSink(self), style)
+    }
+
+    /// Write styled text. If the text is empty, the reset operation will not be performed.
+    fn write_text_style(&mut self, style: &Style, text: &str) -> io::Result<()> {
+        encode::write_text_style(&mut IoSink(self), style, SEMICOLON, text)
+    }
+
+    /// Write styled formatted text. The style is reseted at the end of operation.
+    fn write_text_style_fmt(&mut self, style: &Style, fmt: fmt::Arguments<'_>) -> io::Result<()> {
+        encode::write_text_style_fmt(&mut IoSink(self), style, SEMICOLON, fmt)
+    }
+
+    /// Move the cursor n cells up using ESC[nA.
+    fn cursor_up(&mut self, n: u16) -> io::Result<()> {
+        encode::write_csi(&mut IoSink(self), n, 'A')
+    }
+
+    /// Move the cursor n cells down using ESC[nB.
+    fn cursor_down(&mut self, n: u16) -> io::Result<()> {
+        encode::write_csi(&mut IoSink(self), n, 'B')
+    }
+
+    /// Move the cursor n cells forward using ESC[nC.
+    fn cursor_forward(&mut self, n: u16) -> io::Result<()> {
+        encode::write_csi(&mut IoSink(self), n, 'C')
+    }
+
+    /// Move the cursor n cells back using ESC[nD.
+    fn cursor_back(&mut self, n: u16) -> io::Result<()> {
+        encode::write_csi(&mut IoSink(self), n, 'D')
+    }
+
+    /// Move the cursor to row and column using ESC[row;colH. Both values are 1-based.
+    fn cursor_position(&mut self, row: u16, col: u16) -> io::Result<()> {
+        encode::cursor_position(&mut IoSink(self), row, col)
+    }
+
+    /// Save the cursor position using ESC7.
+    fn save_cursor_position(&mut self) -> io::Result<()> {
+        self.write_all(b"\x1b7")
+    }
+
+    /// Restore the cursor position saved by `save_cursor_position` using ESC8.
+    fn restore_cursor_position(&mut self) -> io::Result<()> {
+        self.write_all(b"\x1b8")
+    }
+
+    /// Hide the cursor using ESC[?25l.
+    fn hide_cursor(&mut self) -> io::Result<()> {
+        self.write_all(b"\x1b[?25l")
+    }
+
+    /// Show the cursor using ESC[?25h.
+    fn show_cursor(&mut self) -> io::Result<()> {
+        self.write_all(b"\x1b[?25h")
+    }
+
+    /// Clear the entire screen using ESC[2J. The cursor position is not changed.
+    fn clear_screen(&mut self) -> io::Result<()> {
+        encode::write_csi(&mut IoSink(self), 2, 'J')
+    }
+
+    /// Clear the entire line using ESC[2K. The cursor position is not changed.
+    fn clear_line(&mut self) -> io::Result<()> {
+        encode::write_csi(&mut IoSink(self), 2, 'K')
+    }
+
+    /// Set the terminal window title using the OSC 0 sequence. Control characters of the title
+    /// are not written.
+    fn set_title(&mut self, title: &str) -> io::Result<()> {
+        encode::set_title(&mut IoSink(self), title)
+    }
+
+    /// Write text as a hyperlink to url using the OSC 8 sequence. Control characters of the url
+    /// are not written.
+    fn write_hyperlink(&mut self, url: &str, text: &str) -> io::Result<()> {
+        encode::write_hyperlink(&mut IoSink(self), url, text)
+    }
+
+    /// Write style markup, like `[bold red]error:[/] text`. The markup is validated before
+    /// anything is written, see `Markup` for its syntax.
+    fn write_markup(&mut self, markup: &str) -> Result<()> {
+        markup::write_markup(
+            &mut IoSink(self),
+            markup,
+            SEMICOLON,
+            true,
+            &mut markup::keep,
+        )?;
+        Ok(())
+    }
+}
+
+impl<W: Write + ?Sized> AnsiWrite for W {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BC_DARK_GRAY, BC_GREEN, FC_GREEN, FC_MAGENTA, FC_YELLOW, TS_BOLD};
+
+    #[test]
+    fn test_write_attributes() {
+        let mut buffer = Vec::<u8>::new();
+        buffer.write_attribute(TS_BOLD).unwrap();
+        buffer.reset_attribute(TS_BOLD).unwrap();
+        buffer.write_font(2).unwrap();
+        buffer.reset_all_attributes().unwrap();
+        assert_eq!(b"\x1b[1m\x1b[22m\x1b[12m\x1b[0m", buffer.as_slice());
+    }
+
+    #[test]
+    fn test_write_text_16_colors() {
+        let mut buffer = Vec::<u8>::new();
+        buffer
+            .write_text_fc_fmt(BC_GREEN, format_args!("a"))
+            .unwrap();
+        buffer
+            .write_text_bc_fmt(FC_YELLOW, format_args!("b"))
+            .unwrap();
+        buffer
+            .write_text_color(FC_MAGENTA, BC_DARK_GRAY, "c")
+            .unwrap();
+        assert_eq!(
+            b"\x1b[32ma\x1b[39m\x1b[103mb\x1b[49m\x1b[35;100mc\x1b[39m\x1b[49m",
+            buffer.as_slice()
+        );
+    }
+
+    #[test]
+    fn test_write_text_rich_colors() {
+        let mut buffer = Vec::<u8>::new();
+        buffer.write_text_fc256(FC_GREEN, "a").unwrap();
+        buffer.write_text_bcrgb(1, 2, 3, "").unwrap();
+        buffer
+            .write_color_rgb(FC_RICH_COLORS, 4, 5, 6, ColorEncoding::ColonWithColorSpace)
+            .unwrap();
+        assert_eq!(
+            b"\x1b[38;5;32ma\x1b[39m\x1b[48;2;1;2;3m\x1b[38:2::4:5:6m",
+            buffer.as_slice()
+        );
+    }
+
+    #[test]
+    fn test_cursor_operations() {
+        let mut buffer = Vec::<u8>::new();
+        buffer.cursor_up(1).unwrap();
+        buffer.cursor_down(2).unwrap();
+        buffer.cursor_forward(3).unwrap();
+        buffer.cursor_back(4).unwrap();
+        buffer.cursor_position(5, 6).unwrap();
+        buffer.save_cursor_position().unwrap();
+        buffer.restore_cursor_position().unwrap();
+        buffer.hide_cursor().unwrap();
+        buffer.show_cursor().unwrap();
+        buffer.clear_screen().unwrap();
+        buffer.clear_line().unwrap();
+        assert_eq!(
+            b"\x1b[1A\x1b[2B\x1b[3C\x1b[4D\x1b[5;6H\x1b7\x1b8\x1b[?25l\x1b[?25h\x1b[2J\x1b[2K",
+            buffer.as_slice()
+        );
+    }
+
+    #[test]
+    fn test_osc_operations() {
+        let mut buffer = Vec::<u8>::new();
+        buffer.set_title("ansi").unwrap();
+        buffer.write_hyperlink("https://x", "link").unwrap();
+        assert_eq!(
+            b"\x1b]0;ansi\x1b\\\x1b]8;;https://x\x1b\\link\x1b]8;;\x1b\\",
+            buffer.as_slice()
+        );
+        buffer.clear();
+        // control characters can not end the sequence early
+        buffer.set_title("a\x07b\x1b]0;c\x7f\n").unwrap();
+        buffer
+            .write_hyperlink("https://x\x1b\\y", "\x1b[1m")
+            .unwrap();
+        assert_eq!(
+            b"\x1b]0;ab]0;c\x1b\\\x1b]8;;https://x\\y\x1b\\\x1b[1m\x1b]8;;\x1b\\",
+            buffer.as_slice()
+        );
+    }
+}